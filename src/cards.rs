@@ -1,8 +1,37 @@
 use std::collections::HashSet;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::OnceLock;
+use rand::Rng;
+use rand::thread_rng;
+use log::{_json_field, _json_object_array};
+use rational::Rational;
+
+/**
+    The rules that vary between Quantum Go Fish variants: how many
+    cards of each suit exist in the pack, how many cards a hand starts
+    with, and how many suits are in play. The standard game has
+    `cards_per_suit == hand_size == 4` and one suit per player.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameConfig {
+    pub cards_per_suit: i8,
+    pub hand_size: i8,
+    pub number_of_suits: i8,
+}
+
+impl GameConfig {
+    pub fn new(cards_per_suit: i8, hand_size: i8, number_of_suits: i8) -> GameConfig {
+        GameConfig { cards_per_suit, hand_size, number_of_suits }
+    }
+
+    /** The standard rules: four of a kind, four-card hands, one suit per player. */
+    pub fn standard(number_of_players: usize) -> GameConfig {
+        GameConfig { cards_per_suit: 4, hand_size: 4, number_of_suits: number_of_players as i8 }
+    }
+}
 
-/** 
+/**
     Represents a single hand of cards. Cards are either of a
     known suit, or are one of a set of possibilities. We
     define the possibilities in terms of what we know they are
@@ -14,6 +43,15 @@ pub struct Hand {
     pub known_cards: HashMap<i8, i8>,
     pub known_voids: HashSet<i8>,
     pub number_of_unknown_cards: i8,
+    pub cards_per_suit: i8,
+    /**
+        The number of cards this hand was originally dealt. Unlike
+        `number_of_unknown_cards`, which only ever falls as cards become
+        known, this never changes, so it is the correct upper bound for
+        `number_of_unknown_cards` -- `cards_per_suit` is the wrong bound
+        whenever a `GameConfig` decouples the two.
+    */
+    pub hand_size: i8,
 }
 
 impl fmt::Display for Hand {
@@ -38,14 +76,25 @@ impl fmt::Display for Hand {
 }
 
 impl Hand {
-    /** 
-        Creates an empty hand
+    /**
+        Creates an empty hand under the standard rules: four unknown
+        cards, drawn from suits that hold four cards each.
     */
     pub fn new() -> Hand {
+        Hand::with_config(4, 4)
+    }
+
+    /**
+        Creates an empty hand of `hand_size` unknown cards, drawn from
+        suits that hold `cards_per_suit` cards each.
+    */
+    pub fn with_config(hand_size: i8, cards_per_suit: i8) -> Hand {
         Hand {
             known_cards : HashMap::new(),
             known_voids : HashSet::new(),
-            number_of_unknown_cards: 4,
+            number_of_unknown_cards: hand_size,
+            cards_per_suit: cards_per_suit,
+            hand_size: hand_size,
         }
     }
 
@@ -136,12 +185,13 @@ impl Hand {
         *self.known_cards.entry(suit).or_insert(0) += 1;
     }
 
-    /** 
-        Returns true if this hand contains four of a kind.
+    /**
+        Returns true if this hand contains a complete set of some suit
+        (four of a kind in the standard game).
     */
-    pub fn has_four_of_a_kind(&mut self) -> bool {
+    pub fn has_complete_set(&mut self) -> bool {
         for (_, &count) in self.known_cards.iter() {
-            if count == 4 {
+            if count == self.cards_per_suit {
                 return true;
             }
         }
@@ -234,30 +284,13 @@ impl Hand {
         }
     }
 
-    /** 
-        If only one of the hands has any unknowns in it, we
-        can fill them given the counts of other cards. Returns
-        True if it cannot be done.
-    */
-    pub fn fill_unknowns(&mut self, totals: &mut HashMap<i8, i8>) -> bool {
-        for (&suit, &count) in totals.iter() {
-            if count < 4 {
-                if !self.fill_some_unknowns(suit, 4 - count) {
-                    return false
-                }
-            }
-        }
-        assert!(self.number_of_unknown_cards == 0);
-        return true
-    }
-
-    /** 
+    /**
         Fill in some of the unknowns in a given hand with the
         given suit. Returns False if
         it cannot be done.
     */
     pub fn fill_some_unknowns(&mut self, suit: i8, count: i8) -> bool {
-        assert!(count <= 4);
+        assert!(count <= self.cards_per_suit);
         if self.number_of_unknown_cards < count {
             return false;
         }
@@ -276,13 +309,21 @@ impl Hand {
         the count of cards in any suit must be less than four.
     */
     pub fn position(&self, mut pos: i128, permutation: &[i8]) -> i128 {
+        let radix = self.cards_per_suit as i128;
         for i in permutation {
             let count = self.known_cards.get(&i).cloned().unwrap_or(0);
-            assert!(count >= 0 && count < 4);
-            pos *= 4;
+            assert!(count >= 0 && (count as i128) < radix);
+            pos *= radix;
             pos += count as i128;
         }
-        pos *= 8;
+        // number_of_unknown_cards only ever falls from its starting value
+        // of hand_size, so hand_size (not cards_per_suit) is its true
+        // upper bound -- a GameConfig that decouples the two would
+        // otherwise let this digit overflow into the known-card digits
+        // above.
+        let unknown_radix = self.hand_size as i128 + 1;
+        assert!(self.number_of_unknown_cards >= 0 && (self.number_of_unknown_cards as i128) < unknown_radix);
+        pos *= unknown_radix;
         pos += self.number_of_unknown_cards as i128;
         for i in permutation {
             let count = if self.known_voids.contains(i) { 1 } else { 0 };
@@ -319,42 +360,185 @@ impl Hand {
             }
         }
     }
+
+    /**
+        A cheap Zobrist-style fingerprint of this hand's known cards,
+        known voids and unknown count, built by XOR-ing together a
+        fixed table of random `u64`s keyed by each feature present.
+        Deliberately does not depend on which seat this hand belongs
+        to, so that `Cards::zobrist_hash`, which XORs these together
+        across all hands, comes out identical whichever hand the deal
+        starts from -- the same rotation symmetry `canonical_position`
+        already exploits, for free.
+    */
+    fn _zobrist_key(&self, number_of_suits: i8) -> u64 {
+        let mut key = _zobrist_unknown_key(self.number_of_unknown_cards);
+        for suit in 0..number_of_suits {
+            if let Some(&count) = self.known_cards.get(&suit) {
+                key ^= _zobrist_card_key(suit, count);
+            }
+            if self.known_voids.contains(&suit) {
+                key ^= _zobrist_void_key(suit);
+            }
+        }
+        key
+    }
+
+    /** Serializes this hand's known cards, known voids and unknown count as a JSON object. */
+    fn to_json(&self) -> String {
+        let mut s = String::new();
+        s.push_str("{\"known_cards\":{");
+        for (i, (suit, count)) in self.known_cards.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            s.push_str(&format!("\"{}\":{}", suit, count));
+        }
+        s.push_str("},\"known_voids\":[");
+        for (i, suit) in self.known_voids.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            s.push_str(&suit.to_string());
+        }
+        s.push_str("],\"number_of_unknown_cards\":");
+        s.push_str(&self.number_of_unknown_cards.to_string());
+        s.push_str(",\"cards_per_suit\":");
+        s.push_str(&self.cards_per_suit.to_string());
+        s.push_str(",\"hand_size\":");
+        s.push_str(&self.hand_size.to_string());
+        s.push('}');
+        s
+    }
+
+    /** Parses a `Hand` from the format written by `to_json`. */
+    fn from_json(obj: &str) -> Result<Hand, String> {
+        let known_cards = _json_int_map(obj, "known_cards")?;
+        let known_voids = _json_int_set(obj, "known_voids")?;
+        let number_of_unknown_cards = _json_field(obj, "number_of_unknown_cards")?
+            .parse::<i8>().map_err(|e| e.to_string())?;
+        let cards_per_suit = _json_field(obj, "cards_per_suit")?
+            .parse::<i8>().map_err(|e| e.to_string())?;
+        let hand_size = _json_field(obj, "hand_size")?
+            .parse::<i8>().map_err(|e| e.to_string())?;
+        Ok(Hand { known_cards, known_voids, number_of_unknown_cards, cards_per_suit, hand_size })
+    }
+
+    /**
+        Parses a `Hand` from the compact notation also produced by this
+        type's `Display`: each digit is one known card of that suit,
+        `?` is one unknown card, and a trailing `x` followed by digits
+        marks those suits as known voids, e.g. `222?x01`.
+    */
+    fn from_notation(text: &str, cards_per_suit: i8, number_of_suits: i8, hand_size: i8) -> Result<Hand, String> {
+        let (known_part, void_part) = match text.find('x') {
+            Some(i) => (&text[..i], &text[i + 1..]),
+            None => (text, ""),
+        };
+
+        let mut known_cards = HashMap::new();
+        let mut number_of_unknown_cards = 0;
+        for c in known_part.chars() {
+            if c == '?' {
+                number_of_unknown_cards += 1;
+                continue;
+            }
+            let suit = c.to_digit(10)
+                .ok_or_else(|| format!("invalid card '{}' in hand '{}'", c, text))? as i8;
+            if suit >= number_of_suits {
+                return Err(format!("suit {} is out of range in hand '{}'", suit, text));
+            }
+            *known_cards.entry(suit).or_insert(0) += 1;
+        }
+
+        let mut known_voids = HashSet::new();
+        for c in void_part.chars() {
+            let suit = c.to_digit(10)
+                .ok_or_else(|| format!("invalid void suit '{}' in hand '{}'", c, text))? as i8;
+            if suit >= number_of_suits {
+                return Err(format!("void suit {} is out of range in hand '{}'", suit, text));
+            }
+            if known_cards.contains_key(&suit) {
+                return Err(format!("suit {} is both a known card and a known void in hand '{}'", suit, text));
+            }
+            known_voids.insert(suit);
+        }
+
+        Ok(Hand { known_cards, known_voids, number_of_unknown_cards, cards_per_suit, hand_size })
+    }
 }
 
 pub const NO_WINNER: i64 = -1;
 pub const ILLEGAL_CARDS: i64 = -2;
 
-/** 
+/**
     Represents a pack of playing cards, divided by the given number
-    of players. There are four cards per player, and the same
-    number of suits as players.
+    of players, under the rules described by `config` (by default, four
+    cards per player, and the same number of suits as players).
 */
 #[derive(Clone)]
 pub struct Cards {
     pub hands: Vec<Hand>,
+    pub config: GameConfig,
+    /**
+        An incrementally-maintained Zobrist-style fingerprint of the
+        current hands (see `Hand::_zobrist_key`), kept up to date by
+        `transfer`, `no_transfer` and `shake_down`. It is a best-effort
+        pre-filter only: code that assigns `hands` directly bypasses
+        it, and `TranspositionTable` always confirms a hit against the
+        exact canonical position, so a stale or colliding value can
+        only cost an extra cache miss, never a wrong answer.
+    */
+    zobrist: u64,
 }
 
 impl fmt::Display for Cards {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-
-        let mut first = true;
-        for hand in &self.hands {
-            if !first {
-                write!(f, "/")?;
-            }
-            first = false;
-            write!(f, "{}", hand)?;
-        }
-        return Ok(())
+        write!(f, "{}", self.to_notation())
     }
 }
 
 impl Cards {
+    /** Creates a standard deal: four cards per player, one suit per player. */
     pub fn new(number_of_players: usize) -> Cards {
-        let tmp_hands = (0..number_of_players).map(|_| Hand::new()).collect::<Vec<_>>();
-        Cards {
+        Cards::with_config(GameConfig::standard(number_of_players), number_of_players)
+    }
+
+    /** Creates a deal under a custom `GameConfig` (e.g. 3-of-a-kind games, or a number of suits that differs from the number of players). */
+    pub fn with_config(config: GameConfig, number_of_players: usize) -> Cards {
+        let tmp_hands = (0..number_of_players)
+            .map(|_| Hand::with_config(config.hand_size, config.cards_per_suit))
+            .collect::<Vec<_>>();
+        let mut cards = Cards {
             hands: tmp_hands,
-        }
+            config: config,
+            zobrist: 0,
+        };
+        cards._resync_zobrist();
+        cards
+    }
+
+    /**
+        Returns the current value of the incremental Zobrist-style
+        fingerprint (see the `zobrist` field). Intended as a cheap probe
+        for `TranspositionTable`, not as a replacement for the exact
+        canonical position.
+    */
+    pub fn zobrist_hash(&self) -> u64 {
+        self.zobrist
+    }
+
+    /** Recomputes `zobrist` from scratch, for when it is cheaper or safer than tracking deltas. */
+    fn _resync_zobrist(&mut self) {
+        let number_of_suits = self.config.number_of_suits;
+        self.zobrist = self.hands.iter().fold(0u64, |acc, hand| acc ^ hand._zobrist_key(number_of_suits));
+    }
+
+    /** Updates `zobrist` for a hand whose feature key was `old_key` before it was just mutated. */
+    fn _rehash_hand(&mut self, hand: usize, old_key: u64) {
+        let number_of_suits = self.config.number_of_suits;
+        let new_key = self.hands[hand]._zobrist_key(number_of_suits);
+        self.zobrist ^= old_key ^ new_key;
     }
 
     pub fn is_empty(&mut self, player: usize) -> bool {
@@ -383,19 +567,27 @@ impl Cards {
         could be done.
     */
     pub fn transfer(&mut self, suit: i8, other: usize, this: usize, no_throw: bool) -> bool {
-        if !self.hands[this].ensure_have(suit) {
+        let old_this = self.hands[this]._zobrist_key(self.config.number_of_suits);
+        let have = self.hands[this].ensure_have(suit);
+        self._rehash_hand(this, old_this);
+        if !have {
             if no_throw {
                 return false;
             }
             panic!("Cannot ask for {} as we know you don't have any", suit);
         }
-        if !self.hands[other].remove(suit) {
+        let old_other = self.hands[other]._zobrist_key(self.config.number_of_suits);
+        let removed = self.hands[other].remove(suit);
+        self._rehash_hand(other, old_other);
+        if !removed {
             if no_throw {
                 return false;
             }
             assert!(false, "We know player {} doesn't have any {}", other, suit);
         }
+        let old_this_again = self.hands[this]._zobrist_key(self.config.number_of_suits);
         self.hands[this].add(suit);
+        self._rehash_hand(this, old_this_again);
         return true;
     }
 
@@ -406,13 +598,19 @@ impl Cards {
         done.
     */
     pub fn no_transfer(&mut self, suit: i8, other: usize, this: usize, no_throw: bool) -> bool {
-        if !self.hands[this].ensure_have(suit) {
+        let old_this = self.hands[this]._zobrist_key(self.config.number_of_suits);
+        let have = self.hands[this].ensure_have(suit);
+        self._rehash_hand(this, old_this);
+        if !have {
             if no_throw {
                 return false;
             }
             panic!("Cannot ask for {} as we know you don't have any", suit);
         }
-        if !self.hands[other].ensure_have_not(suit) {
+        let old_other = self.hands[other]._zobrist_key(self.config.number_of_suits);
+        let rejected = self.hands[other].ensure_have_not(suit);
+        self._rehash_hand(other, old_other);
+        if !rejected {
             if no_throw {
                 return false;
             }
@@ -424,8 +622,8 @@ impl Cards {
     /** 
         Is there a winner? If so, return the number of
         the winner. If not, return -1. If the number of cards
-        in any suit is greater than 4, or if the hands are
-        illegal for any other reason, return -2.
+        in any suit is greater than `cards_per_suit`, or if the
+        hands are illegal for any other reason, return -2.
     */
     pub fn test_winner(&mut self, last_player: usize) -> i64 {
         if !self.shake_down() {
@@ -444,7 +642,7 @@ impl Cards {
         let n = self.hands.len();
         for i in 0..n {
             let player = (i + last_player) % n;
-            if self.hands[player].has_four_of_a_kind() {
+            if self.hands[player].has_complete_set() {
                 return player as i64;
             }
         }
@@ -456,8 +654,8 @@ impl Cards {
         Returns True if the cards are logically consistent.
     */
     pub fn shake_down(&mut self) -> bool {
-        let len_hands = self.hands.len();
-        let all_suits = (0..len_hands as i8).collect::<HashSet<_>>();
+        let cards_per_suit = self.config.cards_per_suit;
+        let number_of_suits = self.config.number_of_suits;
         let mut any_changes = true;
         while any_changes {
             any_changes = false;
@@ -466,177 +664,235 @@ impl Cards {
                 hand.running_totals(&mut totals);
             }
             for (&suit, &total) in totals.iter() {
-                if any_changes {
-                    break;
-                }
-                if total > 4 {
+                if total > cards_per_suit {
                     return false;
                 }
-                if total == 4 {
+                if total == cards_per_suit {
                     for hand in &mut self.hands {
                         if hand.kill_unknown(suit) {
                             any_changes = true;
                         }
                     }
-                } else {
-                    let mut hands_with_unknowns = vec![];
-                    let mut number_of_unknown_cards = 0;
-                    for (i, hand) in self.hands.iter().enumerate() {
-                        if hand.number_of_unknown_cards > 0 &&
-                                !hand.known_voids.contains(&suit) {
-                            hands_with_unknowns.push(i);
-                            number_of_unknown_cards += hand.number_of_unknown_cards;
-                        }
-                    }
-                    if hands_with_unknowns.len() == 1 {
-                        if !self.hands[hands_with_unknowns[0]].fill_some_unknowns(suit, 4 - total) {
-                            return false;
-                        }
-                        any_changes = true;
-                    } else {
-                        let remainder = 4 - total;
-                        if number_of_unknown_cards < remainder {
-                            return false;
-                        } else {
-                            if number_of_unknown_cards == remainder {
-                                for i in &hands_with_unknowns {
-                                    let hand = &mut self.hands[*i];
-                                    let unknowns = hand.number_of_unknown_cards;
-                                    if !hand.fill_some_unknowns(suit, unknowns) {
-                                        return false;
-                                    }
-                                }
-                                any_changes = true;
-                            }
-                        }
-                    }
                 }
             }
             for hand in &mut self.hands {
-                if hand.force_unknowns(len_hands as i8) {
+                if hand.force_unknowns(number_of_suits) {
                     any_changes = true;
                 }
             }
             if any_changes {
                 continue;
             }
-            let mut hands_with_unknowns = vec![];
-            for (i, hand) in self.hands.iter().enumerate() {
-                if hand.number_of_unknown_cards > 0 {
-                    hands_with_unknowns.push(i);
-                }
-            }
-            if hands_with_unknowns.len() == 1 {
-                if !self.hands[hands_with_unknowns[0]].fill_unknowns(&mut totals) {
-                    return false;
+            match self._shake_down_flow(cards_per_suit, number_of_suits) {
+                None => return false,
+                Some(changed) => {
+                    if changed {
+                        any_changes = true;
+                    }
                 }
-                any_changes = true;
-            }
-            if any_changes {
-                continue;
             }
+        }
+        // shake_down can touch many hands across several passes (kill_unknown,
+        // force_unknowns, and the two _shake_down_flow deductions all mutate
+        // hands directly); rather than thread a zobrist delta through every
+        // one of those branches, just resync from scratch once it converges.
+        self._resync_zobrist();
+        return true;
+    }
 
-            // fill in any missing totals with zero
-            for suit in 0..len_hands as i8 {
-                if !totals.contains_key(&suit) {
-                    totals.insert(suit, 0);
-                }
-            }
+    /**
+        Completes the deductions `shake_down` cannot make by the cheap
+        per-suit checks above: models the remaining unknown cards as a
+        bipartite max-flow problem (source -> suit nodes, with capacity
+        `cards_per_suit - total_known(suit)`; suit -> hand edges, with
+        capacity `min(hand's unknown count, the suit's remaining count)`,
+        present only where the hand is not already known void of that
+        suit; hand -> sink, with capacity the hand's unknown count).
+
+        The position is inconsistent (Hall's condition violated) iff
+        this cannot reach a flow equal to the total number of unknown
+        cards, in which case this returns `None`. Otherwise, each
+        suit/hand edge is tested two ways:
+
+        * its forced minimum is `total_unknowns - max_flow(graph with
+          that edge's capacity set to zero)`: whatever flow is lost by
+          forbidding the edge has to be made up by the edge itself.
+          Whenever that minimum is positive, the suit is filled in via
+          `fill_some_unknowns`.
+        * otherwise, it may still be unusable at any value: pre-commit
+          one card through the edge (taking it out of both the suit's
+          and the hand's remaining totals) and re-run max-flow on what
+          is left. If even that can no longer reach the rest of the
+          total, no feasible completion ever puts this suit in this
+          hand, so the hand is marked void of it.
+
+        Returns `Some(true)` if either kind of deduction changed anything.
+    */
+    fn _shake_down_flow(&mut self, cards_per_suit: i8, number_of_suits: i8) -> Option<bool> {
+        let mut totals = HashMap::new();
+        for hand in &self.hands {
+            hand.running_totals(&mut totals);
+        }
+        let remaining: Vec<i8> = (0..number_of_suits)
+            .map(|s| cards_per_suit - *totals.get(&s).unwrap_or(&0))
+            .collect();
+        let total_demand: i32 = self.hands.iter().map(|h| h.number_of_unknown_cards as i32).sum();
+
+        let (mut capacity, source, sink) = _build_flow_graph(&self.hands, &remaining);
+        if _max_flow(&mut capacity, source, sink) < total_demand {
+            return None;
+        }
 
-            for hand in &mut self.hands {
-                if hand.number_of_unknown_cards > 1 {
-                    let mut possible = 0;
-                    for (&suit, &total) in totals.iter() {
-                        if total < 4 && !hand.known_voids.contains(&suit) {
-                            possible += 4 - total;
-                        }
-                    }
-                    if possible < hand.number_of_unknown_cards {
-                        return false;
-                    }
-                    let unknowns = hand.number_of_unknown_cards;
-                    for (&suit, &total) in totals.iter() {
-                        if total < 4 && !hand.known_voids.contains(&suit) {
-                            let remaining = possible - (4 - total);
-                            if remaining < unknowns {
-                                let min_suit = unknowns - remaining;
-                                if !hand.fill_some_unknowns(suit, min_suit) {
-                                    return false;
-                                }
-                                any_changes = true;
-                            }
-                        }
-                    }
-                }
-            }
-            if any_changes {
+        let suit_base = 1;
+        let hand_base = 1 + number_of_suits as usize;
+        for h in 0..self.hands.len() {
+            if self.hands[h].number_of_unknown_cards == 0 {
                 continue;
             }
-            for (&suit, &total) in totals.iter() {
-                if total > 2 {
+            for s in 0..number_of_suits {
+                if self.hands[h].known_voids.contains(&s) {
                     continue;
                 }
-                let mut slots = 0;
-                for hand in &self.hands {
-                    if !hand.known_voids.contains(&suit) {
-                        slots += hand.number_of_unknown_cards;
-                    }
+                let cap = self.hands[h].number_of_unknown_cards.min(remaining[s as usize]);
+                if cap == 0 {
+                    continue;
                 }
-                for hand in &mut self.hands {
-                    if !hand.known_voids.contains(&suit) {
-                        let other_slots = slots - hand.number_of_unknown_cards;
-                        if other_slots < total {
-                            if !hand.fill_some_unknowns(suit, total - other_slots) {
-                                return false;
-                            }
-                        }
+                let (mut without_edge, source, sink) = _build_flow_graph(&self.hands, &remaining);
+                without_edge[suit_base + s as usize][hand_base + h] = 0;
+                let forced = total_demand - _max_flow(&mut without_edge, source, sink);
+                if forced > 0 {
+                    // Applying this deduction changes the suit/hand totals that
+                    // `remaining` and `total_demand` above were computed from, so
+                    // stop here rather than keep using them stale: the caller's
+                    // `while any_changes` loop will call back in with fresh ones.
+                    if !self.hands[h].fill_some_unknowns(s, forced as i8) {
+                        return None;
                     }
+                    return Some(true);
                 }
-            }
-            if any_changes {
-                continue;
-            }
-            let mut groups : HashMap<Vec<i8>, Vec<usize>> = HashMap::new();
-            for (player, hand) in self.hands.iter().enumerate() {
-                let group_len = hand.known_voids.len();
-                if hand.number_of_unknown_cards > 0 && group_len > 1 {
-                    let group : Vec<i8> = all_suits.difference(&hand.known_voids).cloned().collect();
-                    groups.entry(group).or_insert(vec![]).push(player);
+
+                // The edge isn't forced to a positive minimum, but it might
+                // still be unusable at any value: pre-commit one card through
+                // it (taking that card out of the suit's and the hand's
+                // totals) and check whether the rest of the graph can still
+                // cover the remaining demand. If not, no feasible completion
+                // ever puts this suit in this hand, so the hand is void of it.
+                let (mut precommitted, source, sink) = _build_flow_graph(&self.hands, &remaining);
+                precommitted[source][suit_base + s as usize] -= 1;
+                precommitted[hand_base + h][sink] -= 1;
+                precommitted[suit_base + s as usize][hand_base + h] = 0;
+                if _max_flow(&mut precommitted, source, sink) + 1 < total_demand {
+                    self.hands[h].known_voids.insert(s);
+                    return Some(true);
                 }
             }
-            for (group, players) in groups.iter() {
-                if players.len() > 1 {
-                    let mut missing = group.len() as i8 * 4;
-                    for (&suit, &total) in totals.iter() {
-                        if group.iter().find(|&x| *x == suit) != None {
-                            missing -= total as i8;
-                        }
-                    }
-                    let mut holes = 0;
-                    for &player in players {
-                        holes += self.hands[player].number_of_unknown_cards as i8;
-                    }
-                    if missing < holes {
-                        return false;
-                    }
-                    if missing == holes {
-                        for (player, hand) in self.hands.iter_mut().enumerate() {
-                            if players.iter().find(|&x| *x == player) == None {
-                                for suit in group {
-                                    if hand.kill_unknown(*suit) {
-                                        any_changes = true;
-                                    }
-                                }
-                            }
-                        }
-                    }
+        }
+        Some(false)
+    }
+
+    /**
+        An exact legality test, independent of `shake_down`'s opportunistic
+        per-suit and max-flow checks: models the position as a bipartite
+        transportation problem with lower bounds -- suits supply exactly
+        `cards_per_suit` cards each, hands demand their known card count
+        plus their `number_of_unknown_cards`, and a suit/hand edge (absent
+        where the hand is a known void) has a lower bound equal to the
+        hand's already-known count of that suit and an upper bound of
+        `cards_per_suit` -- and solves it via the standard super-source
+        / super-sink reduction for lower-bounded max-flow: a feasible
+        deal exists iff the reduced network saturates every edge out of
+        the super source. See `_feasibility_violation` for the cut this
+        reports when it doesn't.
+    */
+    pub fn is_feasible(&self) -> bool {
+        self._feasibility_violation().is_none()
+    }
+
+    /**
+        Like `is_feasible`, but on infeasibility names a suit or hand
+        whose constraint the min cut violates, found by searching the
+        residual graph for a node whose super-source edge is unsaturated.
+        A caller such as `shake_down` could turn a `Hand` violation into
+        a forced `known_void`, or a `Suit` violation into a diagnosis of
+        which suit is over-committed.
+    */
+    fn _feasibility_violation(&self) -> Option<FeasibilityViolation> {
+        let cards_per_suit = self.config.cards_per_suit as i32;
+        let number_of_suits = self.config.number_of_suits as usize;
+        let num_hands = self.hands.len();
+
+        let source = 0;
+        let suit_base = 1;
+        let hand_base = suit_base + number_of_suits;
+        let sink = hand_base + num_hands;
+        let super_source = sink + 1;
+        let super_sink = sink + 2;
+        let n = super_sink + 1;
+
+        let mut capacity = vec![vec![0i32; n]; n];
+        let mut excess = vec![0i32; n];
+
+        // source -> suit[s]: lower == upper == cards_per_suit, so the
+        // reduced capacity is zero; only the excess bookkeeping matters.
+        for s in 0..number_of_suits {
+            excess[suit_base + s] += cards_per_suit;
+            excess[source] -= cards_per_suit;
+        }
+
+        // suit[s] -> hand[h]: lower = known_cards[s], upper = cards_per_suit.
+        for (h, hand) in self.hands.iter().enumerate() {
+            for s in 0..number_of_suits {
+                if hand.known_voids.contains(&(s as i8)) {
+                    continue;
                 }
+                let lower = *hand.known_cards.get(&(s as i8)).unwrap_or(&0) as i32;
+                capacity[suit_base + s][hand_base + h] = cards_per_suit - lower;
+                excess[hand_base + h] += lower;
+                excess[suit_base + s] -= lower;
             }
         }
-        return true;
+
+        // hand[h] -> sink: lower == upper == known count + unknown count.
+        for (h, hand) in self.hands.iter().enumerate() {
+            let known: i32 = hand.known_cards.values().map(|&c| c as i32).sum();
+            let demand = known + hand.number_of_unknown_cards as i32;
+            excess[sink] += demand;
+            excess[hand_base + h] -= demand;
+        }
+
+        // sink -> source: unbounded, so the lower-bounded network can be
+        // treated as a circulation (the standard reduction's last step).
+        capacity[sink][source] = i32::max_value() / 4;
+
+        let mut total_excess = 0i32;
+        for v in 0..n {
+            if excess[v] > 0 {
+                capacity[super_source][v] = excess[v];
+                total_excess += excess[v];
+            } else if excess[v] < 0 {
+                capacity[v][super_sink] = -excess[v];
+            }
+        }
+
+        if _max_flow(&mut capacity, super_source, super_sink) >= total_excess {
+            return None;
+        }
+
+        let reachable = _reachable_from(&capacity, super_source);
+        for s in 0..number_of_suits {
+            if excess[suit_base + s] > 0 && reachable[suit_base + s] {
+                return Some(FeasibilityViolation::Suit(s as i8));
+            }
+        }
+        for h in 0..num_hands {
+            if excess[hand_base + h] > 0 && reachable[hand_base + h] {
+                return Some(FeasibilityViolation::Hand(h));
+            }
+        }
+        None
     }
 
-    /** 
+    /**
         Is this move legal?
     */
     pub fn legal(&self, other: usize, suit: i8, this: usize, verbose: bool) -> bool {
@@ -647,7 +903,7 @@ impl Cards {
         if this >= n || other >= n {
             return _not_legal(verbose, "Player number out of range");
         }
-        if suit < 0 || suit >= n as i8 {
+        if suit < 0 || suit >= self.config.number_of_suits {
             return _not_legal(verbose, "Suit number out of range");
         }
         if !self.hands[this].is_legal(suit) {
@@ -691,7 +947,7 @@ impl Cards {
                         if !this_hand.known_cards.contains_key(&suit) {
                             count += 1;
                         }
-                        if count >= 4 {
+                        if count >= self.config.cards_per_suit {
                             continue;
                         }
                     }
@@ -707,7 +963,7 @@ impl Cards {
         so we can test whether the position repeats.
     */
     pub fn position(&mut self, last_player: usize) -> i128 {
-        let n = self.number_of_players() as i8;
+        let n = self.config.number_of_suits;
         let permutation : Vec<i8> = (0..n).collect();
         return self.position_given_permutation(&permutation, last_player, false);
     }
@@ -738,7 +994,61 @@ impl Cards {
         return pos;
     }
 
-    /** 
+    /**
+        Builds a relabeling-invariant signature for every suit: for each
+        suit, the sequence of (known card count, is void) pairs across
+        the hands in rotation order starting at last_player. Two suits
+        that are still completely unconstrained have the same
+        signature and so are interchangeable; a suit whose ownership is
+        already pinned down by the counts has a signature no other
+        suit can share, so it keeps a stable identity and is never
+        merged with another suit.
+    */
+    fn _suit_signatures(&self, last_player: usize) -> Vec<Vec<(i8, bool)>> {
+        let n = self.hands.len();
+        let number_of_suits = self.config.number_of_suits as usize;
+        assert!(last_player < n);
+        let mut signatures = vec![Vec::with_capacity(n); number_of_suits];
+        for i in 0..n {
+            let hand = &self.hands[(i + last_player) % n];
+            for suit in 0..number_of_suits as i8 {
+                let count = hand.known_cards.get(&suit).cloned().unwrap_or(0);
+                let void = hand.known_voids.contains(&suit);
+                signatures[suit as usize].push((count, void));
+            }
+        }
+        signatures
+    }
+
+    /**
+        Returns the suit ordering used by `canonical_position`: suits
+        are sorted by their signature (see `_suit_signatures`), so two
+        states that differ only by a permutation of still-unconstrained
+        suits produce the same ordering, and hence the same canonical
+        position.
+    */
+    pub fn canonical_permutation(&self, last_player: usize) -> Vec<i8> {
+        let number_of_suits = self.config.number_of_suits;
+        let signatures = self._suit_signatures(last_player);
+        let mut result: Vec<i8> = (0..number_of_suits).collect();
+        result.sort_by(|&a, &b| signatures[a as usize].cmp(&signatures[b as usize]));
+        result
+    }
+
+    /**
+        A relabeling-invariant representation of the current hands,
+        suitable for detecting draws and memoizing search nodes across
+        states that are identical up to a permutation of
+        still-unconstrained suits. Unlike `position`, two states that
+        differ only in which as-yet-unconstrained suit is which hash to
+        the same value.
+    */
+    pub fn canonical_position(&self, last_player: usize) -> i128 {
+        let permutation = self.canonical_permutation(last_player);
+        self.position_given_permutation(&permutation, last_player, false)
+    }
+
+    /**
         Handle permutation of suits by ordering them according to how
         they appear in the hands: the most common suit in the first
         hand, down to the last suit seen. Suits that are not seen at
@@ -747,8 +1057,9 @@ impl Cards {
     */
     pub fn permutation(&self, last_player: usize) -> Vec<i8> {
         let n = self.hands.len();
+        let number_of_suits = self.config.number_of_suits as usize;
         assert!(last_player < n);
-        let mut ranking = vec![0; n];
+        let mut ranking = vec![0; number_of_suits];
         for i in 0..n {
             let hand = &self.hands[(i + last_player) % n];
             hand.adjust_ranking(&mut ranking);
@@ -756,7 +1067,7 @@ impl Cards {
 
         // We want to return a vector that is the ordering of these rankings
         // in reverse order.
-        let mut result : Vec<i8> = (0..n as i8).collect();
+        let mut result : Vec<i8> = (0..number_of_suits as i8).collect();
         result.sort_by(|&a, &b| ranking[b as usize].cmp(&ranking[a as usize]));
         return result
     }
@@ -781,7 +1092,181 @@ impl Cards {
         return (false, false);
     }
 
-    /** 
+    /**
+        For every hand, estimates the probability that each unknown slot
+        holds a given suit, given everything `shake_down` already knows
+        (known cards, known voids, and how many cards remain to be
+        dealt of each suit). This goes beyond `shake_down`'s all-or-nothing
+        deductions: it assigns a likelihood even when the suit of an
+        unknown card cannot be pinned down exactly.
+
+        The unknown cards are modelled as a matrix `x[hand][suit]` of
+        nonnegative integers: each row sums to that hand's number of
+        unknown cards, each column sums to `cards_per_suit - total_known(suit)`,
+        and `x[hand][suit]` is zero wherever `suit` is a known void of
+        `hand`. Every feasible matrix is weighted by the number of ways
+        its row of suit-counts could have been dealt out as a sequence of
+        individual cards (the multinomial coefficient), and the marginal
+        for `(hand, suit)` is the resulting expectation of
+        `x[hand][suit] / unknowns[hand]`.
+
+        Small boards are enumerated exactly. When the product over hands
+        of each row's possible suit-counts (ignoring suit supplies and
+        voids) exceeds `_MARGINALS_EXACT_LIMIT`, the expectation is
+        instead estimated by randomized sequential sampling with rejection.
+    */
+    pub fn marginals(&self) -> Vec<HashMap<i8, f64>> {
+        let number_of_suits = self.config.number_of_suits;
+        let mut result = vec![HashMap::new(); self.hands.len()];
+
+        let hands_with_unknowns: Vec<usize> = (0..self.hands.len())
+            .filter(|&h| self.hands[h].number_of_unknown_cards > 0)
+            .collect();
+        if hands_with_unknowns.is_empty() {
+            return result;
+        }
+
+        let mut totals: HashMap<i8, i8> = HashMap::new();
+        for hand in &self.hands {
+            hand.running_totals(&mut totals);
+        }
+        let remaining: Vec<i8> = (0..number_of_suits)
+            .map(|s| self.config.cards_per_suit - *totals.get(&s).unwrap_or(&0))
+            .collect();
+        let row_sums: Vec<i8> = hands_with_unknowns.iter()
+            .map(|&h| self.hands[h].number_of_unknown_cards)
+            .collect();
+        let allowed: Vec<Vec<bool>> = hands_with_unknowns.iter()
+            .map(|&h| (0..number_of_suits).map(|s| !self.hands[h].known_voids.contains(&s)).collect())
+            .collect();
+
+        let estimate: u64 = row_sums.iter()
+            .map(|&r| _stars_and_bars(r as u64, number_of_suits as u64))
+            .product();
+
+        let mut expected = vec![vec![0f64; number_of_suits as usize]; hands_with_unknowns.len()];
+        if estimate <= _MARGINALS_EXACT_LIMIT {
+            let mut remaining = remaining;
+            let mut total_weight = 0f64;
+            _marginals_exact(&row_sums, &allowed, &mut remaining, &mut expected, &mut total_weight);
+            if total_weight > 0.0 {
+                for row in expected.iter_mut() {
+                    for v in row.iter_mut() {
+                        *v /= total_weight;
+                    }
+                }
+            }
+        } else {
+            _marginals_sampled(&row_sums, &allowed, &remaining, &mut expected);
+        }
+
+        for (i, &h) in hands_with_unknowns.iter().enumerate() {
+            let unknowns = row_sums[i] as f64;
+            let mut marginal = HashMap::new();
+            for s in 0..number_of_suits {
+                let e = expected[i][s as usize];
+                if e > 0.0 {
+                    marginal.insert(s, e / unknowns);
+                }
+            }
+            result[h] = marginal;
+        }
+        result
+    }
+
+    /**
+        The exact probability, over every feasible completion of the
+        unknown cards, that `player` ends up holding a complete set of
+        some suit. Unlike `marginals`, which counts suit-count matrices
+        combinatorially and falls back to sampling on large boards,
+        this enumerates completions by recursive descent -- fixing one
+        unknown card at a time and pruning with `shake_down` -- and
+        returns the exact fraction as a `Rational`, so equal positions
+        compare exactly and long deduction chains never accumulate
+        floating-point error. Intended for small boards only: the
+        search is exponential in the number of unknown cards.
+    */
+    pub fn win_probability(&self, player: usize) -> Rational {
+        let mut favorable: i128 = 0;
+        let mut total: i128 = 0;
+        self._enumerate_completions(&mut |completed| {
+            total += 1;
+            if completed.hands[player].has_complete_set() {
+                favorable += 1;
+            }
+        });
+        if total == 0 {
+            return Rational::zero();
+        }
+        Rational::new(favorable, total)
+    }
+
+    /**
+        The exact probability, over every feasible completion of the
+        unknown cards, that `this` holds a card of `suit`, computed the
+        same way as `win_probability`. `other` is accepted only for
+        symmetry with `has_card`'s signature: the answer depends solely
+        on `this`'s own hand.
+    */
+    pub fn card_probability(&self, suit: i8, this: usize, _other: usize) -> Rational {
+        let mut favorable: i128 = 0;
+        let mut total: i128 = 0;
+        self._enumerate_completions(&mut |completed| {
+            total += 1;
+            if completed.hands[this].known_cards.contains_key(&suit) {
+                favorable += 1;
+            }
+        });
+        if total == 0 {
+            return Rational::zero();
+        }
+        Rational::new(favorable, total)
+    }
+
+    /**
+        Enumerates every feasible completion of the unknown cards,
+        calling `on_complete` once per completion reached. Completions
+        are found by recursive descent: each step picks the first hand
+        with an unknown card left, tries assigning one of its slots to
+        each suit not already a known void of that hand, prunes via
+        `shake_down`, and recurses. A completion is visited once per
+        distinct path of assignments that reaches it. Because
+        `shake_down` can fill in several cards at once (in this hand or
+        any other) after a step, that path count is not in general the
+        multinomial weighting `marginals` applies, so the two can give
+        different probabilities for the same position.
+    */
+    fn _enumerate_completions<F: FnMut(&mut Cards)>(&self, on_complete: &mut F) {
+        let mut cards = self.clone();
+        if cards.shake_down() {
+            cards._enumerate_completions_step(on_complete);
+        }
+    }
+
+    fn _enumerate_completions_step<F: FnMut(&mut Cards)>(&mut self, on_complete: &mut F) {
+        let next_hand = (0..self.hands.len()).find(|&h| self.hands[h].number_of_unknown_cards > 0);
+        let hand = match next_hand {
+            None => {
+                on_complete(self);
+                return;
+            }
+            Some(h) => h,
+        };
+        let number_of_suits = self.config.number_of_suits;
+        for suit in 0..number_of_suits {
+            if self.hands[hand].known_voids.contains(&suit) {
+                continue;
+            }
+            let mut branch = self.clone();
+            branch.hands[hand].number_of_unknown_cards -= 1;
+            *branch.hands[hand].known_cards.entry(suit).or_insert(0) += 1;
+            if branch.shake_down() {
+                branch._enumerate_completions_step(on_complete);
+            }
+        }
+    }
+
+    /**
         Finds the next player who is able to move (has any cards)
     */
     pub fn next_player(&mut self, this_player: usize) -> usize {
@@ -794,6 +1279,93 @@ impl Cards {
         return p;
     }
 
+    /**
+        Serializes the whole position -- the `GameConfig` it was dealt
+        under, plus every hand's known cards, known voids and unknown
+        count -- as a single JSON object, suitable for `from_json` to
+        reload later. This lets an interesting position be saved, shared,
+        and fed back into the solver or the strategy driver without
+        re-deriving it from a move history.
+
+        Hand-rolled against `log`'s `_json_field`/`_json_object_array`
+        helpers rather than `serde`/`serde_json`, neither of which is a
+        dependency of this crate; `GameLog` (the sibling type this was
+        modelled on) already uses the same hand-rolled format, so this
+        keeps the two serializers consistent.
+    */
+    pub fn to_json(&self) -> String {
+        let mut s = String::new();
+        s.push_str("{\"config\":{\"cards_per_suit\":");
+        s.push_str(&self.config.cards_per_suit.to_string());
+        s.push_str(",\"hand_size\":");
+        s.push_str(&self.config.hand_size.to_string());
+        s.push_str(",\"number_of_suits\":");
+        s.push_str(&self.config.number_of_suits.to_string());
+        s.push_str("},\"hands\":[");
+        for (i, hand) in self.hands.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            s.push_str(&hand.to_json());
+        }
+        s.push_str("]}");
+        s
+    }
+
+    /**
+        Formats this deal in the compact notation parsed by
+        `from_notation`: each hand's known cards, unknown cards and
+        known voids (see `Hand`'s `Display`), separated by `/`.
+    */
+    pub fn to_notation(&self) -> String {
+        self.hands.iter().map(|hand| hand.to_string()).collect::<Vec<_>>().join("/")
+    }
+
+    /**
+        Parses a deal of `n_players` hands from the compact notation
+        written by `to_notation`, under the standard `GameConfig`.
+        Hands are separated by `/`; within a hand, each digit is a
+        known card of that suit, `?` is one unknown card, and a
+        trailing `x` followed by digits marks those suits as known
+        voids, e.g. `222?x01/1x23/000??x23/1133??`. Returns a
+        descriptive error, rather than panicking, if a suit index is
+        out of range or a suit is claimed as both known and void.
+    */
+    pub fn from_notation(text: &str, n_players: usize) -> Result<Cards, String> {
+        let config = GameConfig::standard(n_players);
+        let parts: Vec<&str> = text.split('/').collect();
+        if parts.len() != n_players {
+            return Err(format!("expected {} hands separated by '/', found {}", n_players, parts.len()));
+        }
+        let mut hands = Vec::new();
+        for part in &parts {
+            hands.push(Hand::from_notation(part, config.cards_per_suit, config.number_of_suits, config.hand_size)?);
+        }
+        let mut cards = Cards { hands, config, zobrist: 0 };
+        cards._resync_zobrist();
+        Ok(cards)
+    }
+
+    /** Parses a `Cards` position from the format written by `to_json`. */
+    pub fn from_json(text: &str) -> Result<Cards, String> {
+        let config_obj = _json_object(text, "config")?;
+        let cards_per_suit = _json_field(&config_obj, "cards_per_suit")?
+            .parse::<i8>().map_err(|e| e.to_string())?;
+        let hand_size = _json_field(&config_obj, "hand_size")?
+            .parse::<i8>().map_err(|e| e.to_string())?;
+        let number_of_suits = _json_field(&config_obj, "number_of_suits")?
+            .parse::<i8>().map_err(|e| e.to_string())?;
+        let config = GameConfig::new(cards_per_suit, hand_size, number_of_suits);
+
+        let mut hands = Vec::new();
+        for obj in _json_object_array(text, "hands")? {
+            hands.push(Hand::from_json(&obj)?);
+        }
+        let mut cards = Cards { hands, config, zobrist: 0 };
+        cards._resync_zobrist();
+        Ok(cards)
+    }
+
 }
 
 pub fn _not_legal(verbose: bool, message: &str) -> bool {
@@ -803,11 +1375,398 @@ pub fn _not_legal(verbose: bool, message: &str) -> bool {
     return false;
 }
 
+/** How many distinct suits/counts the Zobrist feature tables cover; values outside this range wrap around. */
+const ZOBRIST_RANGE: usize = 32;
+
+/**
+    The fixed random `u64` tables that back `Hand::_zobrist_key`: one
+    entry per (suit, known count) pair, one per (suit, is void), and
+    one per possible number of unknown cards. Built once, from a fixed
+    seed, so the same feature always hashes to the same value for the
+    life of the process -- that's all `Hand::_zobrist_key` needs, since
+    the table is never compared across runs.
+*/
+struct ZobristTables {
+    card_count: Vec<u64>,
+    void: Vec<u64>,
+    unknown_count: Vec<u64>,
+}
+
+fn _zobrist_tables() -> &'static ZobristTables {
+    static TABLES: OnceLock<ZobristTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut state = 0x9E3779B97F4A7C15u64;
+        let mut next_u64 = move || {
+            // splitmix64
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        };
+        ZobristTables {
+            card_count: (0..ZOBRIST_RANGE * ZOBRIST_RANGE).map(|_| next_u64()).collect(),
+            void: (0..ZOBRIST_RANGE).map(|_| next_u64()).collect(),
+            unknown_count: (0..ZOBRIST_RANGE).map(|_| next_u64()).collect(),
+        }
+    })
+}
+
+fn _zobrist_card_key(suit: i8, count: i8) -> u64 {
+    let tables = _zobrist_tables();
+    let s = (suit as usize) % ZOBRIST_RANGE;
+    let c = (count as usize) % ZOBRIST_RANGE;
+    tables.card_count[s * ZOBRIST_RANGE + c]
+}
+
+fn _zobrist_void_key(suit: i8) -> u64 {
+    let tables = _zobrist_tables();
+    tables.void[(suit as usize) % ZOBRIST_RANGE]
+}
+
+fn _zobrist_unknown_key(count: i8) -> u64 {
+    let tables = _zobrist_tables();
+    tables.unknown_count[(count as usize) % ZOBRIST_RANGE]
+}
+
+/** Finds the raw text of a top-level object field, e.g. `"config":{...}`. */
+fn _json_object(text: &str, name: &str) -> Result<String, String> {
+    let key = format!("\"{}\":{{", name);
+    let start = text.find(&key).ok_or_else(|| format!("object {} not found", name))? + key.len() - 1;
+    let rest = &text[start..];
+    let mut depth = 0;
+    for (i, c) in rest.char_indices() {
+        if c == '{' {
+            depth += 1;
+        } else if c == '}' {
+            depth -= 1;
+            if depth == 0 {
+                return Ok(rest[..=i].to_string());
+            }
+        }
+    }
+    Err(format!("unterminated object {}", name))
+}
+
+/** Finds a top-level field holding a JSON object of int keys to int values, e.g. `"known_cards":{"0":2,"1":1}`. */
+fn _json_int_map(text: &str, name: &str) -> Result<HashMap<i8, i8>, String> {
+    let obj = _json_object(text, name)?;
+    let body = &obj[1..obj.len() - 1];
+    let mut map = HashMap::new();
+    if body.trim().is_empty() {
+        return Ok(map);
+    }
+    for pair in body.split(',') {
+        let mut parts = pair.splitn(2, ':');
+        let key = parts.next().ok_or_else(|| format!("malformed entry in {}", name))?
+            .trim().trim_matches('"').parse::<i8>().map_err(|e| e.to_string())?;
+        let value = parts.next().ok_or_else(|| format!("malformed entry in {}", name))?
+            .trim().parse::<i8>().map_err(|e| e.to_string())?;
+        map.insert(key, value);
+    }
+    Ok(map)
+}
+
+/** Finds a top-level field holding a JSON array of ints, e.g. `"known_voids":[2,3]`. */
+fn _json_int_set(text: &str, name: &str) -> Result<HashSet<i8>, String> {
+    let key = format!("\"{}\":[", name);
+    let start = text.find(&key).ok_or_else(|| format!("array {} not found", name))? + key.len();
+    let rest = &text[start..];
+    let end = rest.find(']').ok_or_else(|| format!("unterminated array {}", name))?;
+    let body = &rest[..end];
+    if body.trim().is_empty() {
+        return Ok(HashSet::new());
+    }
+    body.split(',').map(|s| s.trim().parse::<i8>().map_err(|e| e.to_string())).collect()
+}
+
+/** Which side of `Cards::_feasibility_violation`'s min cut is under-supplied. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeasibilityViolation {
+    Suit(i8),
+    Hand(usize),
+}
+
+/**
+    BFS over the residual graph (edges with positive remaining capacity)
+    starting from `start`, used by `Cards::_feasibility_violation` to
+    find the min cut after a failed max-flow.
+*/
+fn _reachable_from(capacity: &Vec<Vec<i32>>, start: usize) -> Vec<bool> {
+    let n = capacity.len();
+    let mut seen = vec![false; n];
+    seen[start] = true;
+    let mut queue = std::collections::VecDeque::new();
+    queue.push_back(start);
+    while let Some(u) = queue.pop_front() {
+        for v in 0..n {
+            if !seen[v] && capacity[u][v] > 0 {
+                seen[v] = true;
+                queue.push_back(v);
+            }
+        }
+    }
+    seen
+}
+
+/**
+    Builds the capacity matrix for `Cards::_shake_down_flow`'s bipartite
+    max-flow model: node 0 is the source, nodes `1..=number_of_suits`
+    are suit nodes, nodes after that are hand nodes (one per entry in
+    `hands`), and the last node is the sink.
+*/
+fn _build_flow_graph(hands: &[Hand], remaining: &[i8]) -> (Vec<Vec<i32>>, usize, usize) {
+    let number_of_suits = remaining.len();
+    let num_hands = hands.len();
+    let n = 2 + number_of_suits + num_hands;
+    let source = 0;
+    let suit_base = 1;
+    let hand_base = 1 + number_of_suits;
+    let sink = n - 1;
+    let mut capacity = vec![vec![0i32; n]; n];
+    for s in 0..number_of_suits {
+        capacity[source][suit_base + s] = remaining[s] as i32;
+    }
+    for (h, hand) in hands.iter().enumerate() {
+        capacity[hand_base + h][sink] = hand.number_of_unknown_cards as i32;
+        if hand.number_of_unknown_cards == 0 {
+            continue;
+        }
+        for s in 0..number_of_suits {
+            if !hand.known_voids.contains(&(s as i8)) && remaining[s] > 0 {
+                capacity[suit_base + s][hand_base + h] = hand.number_of_unknown_cards.min(remaining[s]) as i32;
+            }
+        }
+    }
+    (capacity, source, sink)
+}
+
+/**
+    Computes max flow from `source` to `sink` via Edmonds-Karp
+    (repeated BFS shortest augmenting paths), mutating `capacity` into
+    its residual graph as it goes.
+*/
+fn _max_flow(capacity: &mut Vec<Vec<i32>>, source: usize, sink: usize) -> i32 {
+    let n = capacity.len();
+    let mut flow = 0;
+    loop {
+        let mut parent: Vec<Option<usize>> = vec![None; n];
+        parent[source] = Some(source);
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back(source);
+        while let Some(u) = queue.pop_front() {
+            if u == sink {
+                break;
+            }
+            for v in 0..n {
+                if parent[v].is_none() && capacity[u][v] > 0 {
+                    parent[v] = Some(u);
+                    queue.push_back(v);
+                }
+            }
+        }
+        if parent[sink].is_none() {
+            break;
+        }
+        let mut path_flow = i32::max_value();
+        let mut v = sink;
+        while v != source {
+            let u = parent[v].unwrap();
+            path_flow = path_flow.min(capacity[u][v]);
+            v = u;
+        }
+        let mut v = sink;
+        while v != source {
+            let u = parent[v].unwrap();
+            capacity[u][v] -= path_flow;
+            capacity[v][u] += path_flow;
+            v = u;
+        }
+        flow += path_flow;
+    }
+    flow
+}
+
+/** Above this many candidate suit-count matrices (counted per row, ignoring suit supplies and voids), `marginals` samples instead of enumerating. */
+const _MARGINALS_EXACT_LIMIT: u64 = 200_000;
+
+fn _factorial(n: i8) -> f64 {
+    (1..=n as i64).fold(1f64, |acc, i| acc * i as f64)
+}
+
+/** The number of ways to place `total` identical items into `bins` bins, used only to bound the search space. */
+fn _stars_and_bars(total: u64, bins: u64) -> u64 {
+    if bins == 0 {
+        return if total == 0 { 1 } else { 0 };
+    }
+    let mut numerator = 1u64;
+    let mut denominator = 1u64;
+    for i in 1..=(bins - 1) {
+        numerator = numerator.saturating_mul(total + i);
+        denominator *= i;
+    }
+    numerator / denominator
+}
+
+/**
+    Recursively enumerates every feasible suit-count row for each hand
+    with unknown cards, in turn, accumulating `expected[hand][suit] +=
+    weight * count` and `total_weight += weight` for each complete,
+    feasible assignment, where `weight` is the multinomial coefficient
+    for how that hand's row could have been dealt as a card sequence.
+*/
+fn _marginals_exact(
+        row_sums: &[i8],
+        allowed: &[Vec<bool>],
+        remaining: &mut [i8],
+        expected: &mut [Vec<f64>],
+        total_weight: &mut f64) {
+
+    if row_sums.is_empty() {
+        return;
+    }
+    let mut rows = Vec::with_capacity(row_sums.len());
+    let mut cur_row = Vec::with_capacity(allowed[0].len());
+    _marginals_step(row_sums, allowed, remaining, 0, 0, row_sums[0], &mut cur_row, &mut rows, expected, total_weight);
+}
+
+/**
+    One step of `_marginals_exact`'s search: fills in `cur_row[suit..]`
+    for `hand`'s remaining suits, then moves on to the next hand once
+    the row is complete, backtracking over `remaining` capacity as it
+    goes.
+*/
+fn _marginals_step(
+        row_sums: &[i8],
+        allowed: &[Vec<bool>],
+        remaining: &mut [i8],
+        hand: usize,
+        suit: usize,
+        row_sum_remaining: i8,
+        cur_row: &mut Vec<i8>,
+        rows: &mut Vec<Vec<i8>>,
+        expected: &mut [Vec<f64>],
+        total_weight: &mut f64) {
+
+    let number_of_suits = remaining.len();
+    if suit == number_of_suits {
+        if row_sum_remaining != 0 {
+            return;
+        }
+        rows.push(cur_row.clone());
+        if hand + 1 == row_sums.len() {
+            let weight = rows.iter().zip(row_sums.iter())
+                .map(|(r, &rs)| _factorial(rs) / r.iter().map(|&c| _factorial(c)).product::<f64>())
+                .product::<f64>();
+            *total_weight += weight;
+            for (h, r) in rows.iter().enumerate() {
+                for (s, &c) in r.iter().enumerate() {
+                    expected[h][s] += weight * c as f64;
+                }
+            }
+        } else {
+            let mut next_row = Vec::with_capacity(number_of_suits);
+            _marginals_step(row_sums, allowed, remaining, hand + 1, 0, row_sums[hand + 1], &mut next_row, rows, expected, total_weight);
+        }
+        rows.pop();
+        return;
+    }
+
+    let cap = if allowed[hand][suit] { remaining[suit].min(row_sum_remaining) } else { 0 };
+    for c in 0..=cap {
+        remaining[suit] -= c;
+        cur_row.push(c);
+        _marginals_step(row_sums, allowed, remaining, hand, suit + 1, row_sum_remaining - c, cur_row, rows, expected, total_weight);
+        cur_row.pop();
+        remaining[suit] += c;
+    }
+}
+
+/**
+    Estimates `expected[hand][suit]` by repeated randomized sequential
+    filling: for each hand in turn, each of its unknown slots is
+    assigned a uniformly random suit among those still allowed and with
+    spare capacity; if no suit remains for some slot, the whole sample
+    is rejected and retried. Used above `_MARGINALS_EXACT_LIMIT`, where
+    full enumeration would be too slow.
+*/
+fn _marginals_sampled(row_sums: &[i8], allowed: &[Vec<bool>], remaining: &[i8], expected: &mut [Vec<f64>]) {
+    const SAMPLES: usize = 4000;
+    const MAX_ATTEMPTS: usize = SAMPLES * 50;
+    let number_of_suits = remaining.len();
+    let mut rng = thread_rng();
+    let mut accepted = 0usize;
+    let mut attempts = 0usize;
+
+    while accepted < SAMPLES && attempts < MAX_ATTEMPTS {
+        attempts += 1;
+        let mut capacity = remaining.to_vec();
+        let mut sample = vec![vec![0i8; number_of_suits]; row_sums.len()];
+        let mut feasible = true;
+
+        'hands: for h in 0..row_sums.len() {
+            for _ in 0..row_sums[h] {
+                let choices: Vec<usize> = (0..number_of_suits)
+                    .filter(|&s| allowed[h][s] && capacity[s] > 0)
+                    .collect();
+                if choices.is_empty() {
+                    feasible = false;
+                    break 'hands;
+                }
+                let s = choices[rng.gen_range(0..choices.len())];
+                capacity[s] -= 1;
+                sample[h][s] += 1;
+            }
+        }
+
+        if feasible {
+            accepted += 1;
+            for h in 0..row_sums.len() {
+                for s in 0..number_of_suits {
+                    expected[h][s] += sample[h][s] as f64;
+                }
+            }
+        }
+    }
+
+    if accepted > 0 {
+        for row in expected.iter_mut() {
+            for v in row.iter_mut() {
+                *v /= accepted as f64;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    /** 
+    /**
+        With a `GameConfig` that decouples `hand_size` from
+        `cards_per_suit` (e.g. ten-card hands dealt from three-card
+        suits), `number_of_unknown_cards` can reach `2 * cards_per_suit` or more.
+        `Hand::position` must size that digit off `hand_size`, or two
+        hands with a different split between known and unknown cards
+        alias to the same position.
+    */
+    #[test]
+    pub fn test_position_with_decoupled_hand_size_does_not_alias() {
+        let cards_per_suit = 3;
+        let hand_size = 10;
+        let permutation = [0, 1];
+
+        let mut all_unknown = Hand::with_config(hand_size, cards_per_suit);
+        all_unknown.number_of_unknown_cards = 6;
+
+        let mut one_known = Hand::with_config(hand_size, cards_per_suit);
+        one_known.known_cards = [(1, 1)].iter().cloned().collect::<HashMap<_, _>>();
+        one_known.number_of_unknown_cards = 0;
+
+        assert!(all_unknown.position(0, &permutation) != one_known.position(0, &permutation));
+    }
+
+    /**
         Tests the case where we have 000/22?/111???x0
         and player 1 asks player 0 for a 1.
     */
@@ -865,7 +1824,25 @@ mod tests {
         println!("test_no_transfer_2: succeeded");
     }
 
-    /** 
+    /**
+        `transfer` and `no_transfer` XOR the affected hands' Zobrist
+        keys in and out rather than recomputing the whole fingerprint,
+        so after a few moves the incrementally-maintained `zobrist`
+        field must still agree with a from-scratch resync.
+    */
+    #[test]
+    pub fn test_zobrist_hash_is_maintained_incrementally() {
+        let mut cards = Cards::new(3);
+        cards.transfer(0, 1, 0, false);
+        cards.no_transfer(1, 2, 1, false);
+        cards.transfer(2, 0, 1, false);
+        let incremental = cards.zobrist_hash();
+        cards._resync_zobrist();
+        assert_eq!(cards.zobrist_hash(), incremental);
+        println!("test_zobrist_hash_is_maintained_incrementally: succeeded");
+    }
+
+    /**
         Tests the cards 00???/??? for whether they are
         consistent. Of course they are.
     */
@@ -921,7 +1898,50 @@ mod tests {
         println!("test_shake_down: succeeded");
     }
 
-    /** 
+    #[test]
+    pub fn test_is_feasible_fresh_deal() {
+        let cards = Cards::new(3);
+        assert!(cards.is_feasible());
+    }
+
+    /**
+        ????x01/???? can't be completed: in a two-player game
+        there are only two suits, and player 0 claims to be
+        void of both of them, leaving nowhere for its four
+        unknown cards to come from.
+    */
+    #[test]
+    pub fn test_is_feasible_hand_with_no_suits_left() {
+        let mut h0 = Hand::new();
+        h0.number_of_unknown_cards = 4;
+        h0.known_voids = [0, 1].iter().cloned().collect::<HashSet<_>>();
+        let mut h1 = Hand::new();
+        h1.number_of_unknown_cards = 4;
+        let mut cards = Cards::new(2);
+        cards.hands = vec![h0, h1];
+        assert!(!cards.is_feasible());
+        assert!(cards._feasibility_violation().is_some());
+    }
+
+    /**
+        0000/0??? claims all four 0s for player 0 while player
+        1 is also claiming a 0, which would be a fifth -- more
+        than the `cards_per_suit` the suit actually has.
+    */
+    #[test]
+    pub fn test_is_feasible_suit_oversubscribed() {
+        let mut h0 = Hand::new();
+        h0.known_cards = [(0, 4)].iter().cloned().collect::<HashMap<_, _>>();
+        h0.number_of_unknown_cards = 0;
+        let mut h1 = Hand::new();
+        h1.known_cards = [(0, 1)].iter().cloned().collect::<HashMap<_, _>>();
+        h1.number_of_unknown_cards = 3;
+        let mut cards = Cards::new(2);
+        cards.hands = vec![h0, h1];
+        assert!(!cards.is_feasible());
+    }
+
+    /**
         Given the cards 00??/01?/11??? is it legal for
         player 2 to tell player 1 that he does not have any
         of suit 2? (It cannot be as that leaves only 3 slots
@@ -1036,7 +2056,7 @@ mod tests {
             (1, 2),
             (3, 2),
             ].iter().cloned().collect::<HashMap<_, _>>();
-        h3.number_of_unknown_cards = 2;
+        h3.number_of_unknown_cards = 1;
         let mut cards = Cards::new(4);
         cards.hands = vec![
             h0,
@@ -1235,4 +2255,171 @@ mod tests {
         assert!(cards.hands[0].number_of_unknown_cards == 0);
         assert!(cards.hands[2].number_of_unknown_cards == 0);
     }
+
+    /**
+        Two hands, two suits, each hand has three known cards of its
+        own suit and one unknown. The one remaining card of each suit
+        must go to one hand or the other, so each unknown is a 50/50
+        between the two suits.
+    */
+    #[test]
+    pub fn test_marginals_symmetric() {
+        let mut h0 = Hand::new();
+        h0.known_cards = [(0, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h0.number_of_unknown_cards = 1;
+        let mut h1 = Hand::new();
+        h1.known_cards = [(1, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h1.number_of_unknown_cards = 1;
+        let mut cards = Cards::new(2);
+        cards.hands = vec![h0, h1];
+        let marginals = cards.marginals();
+        assert!((marginals[0][&0] - 0.5).abs() < 1e-9);
+        assert!((marginals[0][&1] - 0.5).abs() < 1e-9);
+        assert!((marginals[1][&0] - 0.5).abs() < 1e-9);
+        assert!((marginals[1][&1] - 0.5).abs() < 1e-9);
+    }
+
+    /**
+        When shake_down would already pin the unknown card down
+        exactly, marginals should agree: probability 1 for the forced
+        suit and no entry at all for any other suit.
+    */
+    #[test]
+    pub fn test_marginals_forced() {
+        let mut h0 = Hand::new();
+        h0.known_cards = [(0, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h0.number_of_unknown_cards = 1;
+        h0.known_voids = [1].iter().cloned().collect::<HashSet<_>>();
+        let mut h1 = Hand::new();
+        h1.known_cards = [(1, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h1.number_of_unknown_cards = 1;
+        let mut cards = Cards::new(2);
+        cards.hands = vec![h0, h1];
+        let marginals = cards.marginals();
+        assert!((marginals[0][&0] - 1.0).abs() < 1e-9);
+        assert!(marginals[0].get(&1).is_none());
+    }
+
+    /**
+        Same fixture as `test_marginals_symmetric`: each hand's one
+        unknown card is a 50/50 between the two suits, so player 0
+        completes a four-of-a-kind in exactly half of the feasible
+        completions.
+    */
+    #[test]
+    pub fn test_win_probability_symmetric() {
+        let mut h0 = Hand::new();
+        h0.known_cards = [(0, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h0.number_of_unknown_cards = 1;
+        let mut h1 = Hand::new();
+        h1.known_cards = [(1, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h1.number_of_unknown_cards = 1;
+        let mut cards = Cards::new(2);
+        cards.hands = vec![h0, h1];
+
+        assert_eq!(cards.win_probability(0), Rational::new(1, 2));
+        // Hand 0 already holds three known cards of suit 0, so it is
+        // certain to hold at least one; whether its unknown card turns
+        // out to be the (otherwise unseen) suit 1 is the 50/50 part.
+        assert_eq!(cards.card_probability(0, 0, 1), Rational::one());
+        assert_eq!(cards.card_probability(1, 0, 1), Rational::new(1, 2));
+    }
+
+    /**
+        Same fixture as `test_marginals_forced`: hand 0's known void of
+        suit 1 pins its unknown card to suit 0, so hand 0 is certain to
+        complete a four-of-a-kind.
+    */
+    #[test]
+    pub fn test_win_probability_forced() {
+        let mut h0 = Hand::new();
+        h0.known_cards = [(0, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h0.number_of_unknown_cards = 1;
+        h0.known_voids = [1].iter().cloned().collect::<HashSet<_>>();
+        let mut h1 = Hand::new();
+        h1.known_cards = [(1, 3)].iter().cloned().collect::<HashMap<_, _>>();
+        h1.number_of_unknown_cards = 1;
+        let mut cards = Cards::new(2);
+        cards.hands = vec![h0, h1];
+
+        assert_eq!(cards.win_probability(0), Rational::one());
+        assert_eq!(cards.card_probability(0, 0, 1), Rational::one());
+        assert_eq!(cards.card_probability(1, 0, 1), Rational::zero());
+    }
+
+    #[test]
+    pub fn test_to_json_from_json_round_trip() {
+        let mut h0 = Hand::new();
+        h0.known_cards = [(0, 2), (1, 1)].iter().cloned().collect::<HashMap<_, _>>();
+        h0.number_of_unknown_cards = 1;
+        h0.known_voids = [2].iter().cloned().collect::<HashSet<_>>();
+        let mut h1 = Hand::new();
+        h1.number_of_unknown_cards = 4;
+        let mut cards = Cards::new(2);
+        cards.hands = vec![h0, h1];
+
+        let json = cards.to_json();
+        let round_tripped = Cards::from_json(&json).unwrap();
+
+        assert_eq!(round_tripped.config, cards.config);
+        assert_eq!(round_tripped.hands.len(), cards.hands.len());
+        for (original, reloaded) in cards.hands.iter().zip(round_tripped.hands.iter()) {
+            assert_eq!(reloaded.known_cards, original.known_cards);
+            assert_eq!(reloaded.known_voids, original.known_voids);
+            assert_eq!(reloaded.number_of_unknown_cards, original.number_of_unknown_cards);
+            assert_eq!(reloaded.cards_per_suit, original.cards_per_suit);
+        }
+    }
+
+    #[test]
+    pub fn test_from_json_missing_field_is_an_error() {
+        let result = Cards::from_json("{\"config\":{\"cards_per_suit\":4,\"hand_size\":4}}");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    pub fn test_to_notation_from_notation_round_trip() {
+        let mut h0 = Hand::new();
+        h0.known_cards = [(0, 2), (1, 1)].iter().cloned().collect::<HashMap<_, _>>();
+        h0.number_of_unknown_cards = 1;
+        h0.known_voids = [2].iter().cloned().collect::<HashSet<_>>();
+        let mut h1 = Hand::new();
+        h1.number_of_unknown_cards = 4;
+        let mut cards = Cards::new(3);
+        cards.hands = vec![h0, h1, Hand::new()];
+
+        let notation = cards.to_notation();
+        let round_tripped = Cards::from_notation(&notation, 3).unwrap();
+
+        assert_eq!(round_tripped.config, cards.config);
+        for (original, reloaded) in cards.hands.iter().zip(round_tripped.hands.iter()) {
+            assert_eq!(reloaded.known_cards, original.known_cards);
+            assert_eq!(reloaded.known_voids, original.known_voids);
+            assert_eq!(reloaded.number_of_unknown_cards, original.number_of_unknown_cards);
+        }
+    }
+
+    #[test]
+    pub fn test_from_notation_example_from_the_docs() {
+        let cards = Cards::from_notation("222?x01/1x23/000??x23/1133??", 4).unwrap();
+        assert_eq!(cards.hands[0].known_cards.get(&2), Some(&3));
+        assert_eq!(cards.hands[0].number_of_unknown_cards, 1);
+        assert_eq!(cards.hands[0].known_voids, [0, 1].iter().cloned().collect::<HashSet<_>>());
+        assert_eq!(cards.hands[1].known_voids, [2, 3].iter().cloned().collect::<HashSet<_>>());
+    }
+
+    #[test]
+    pub fn test_from_notation_wrong_number_of_hands_is_an_error() {
+        assert!(Cards::from_notation("222?/1x23", 4).is_err());
+    }
+
+    #[test]
+    pub fn test_from_notation_suit_out_of_range_is_an_error() {
+        assert!(Cards::from_notation("5555/????/????/????", 4).is_err());
+    }
+
+    #[test]
+    pub fn test_from_notation_void_suit_also_known_is_an_error() {
+        assert!(Cards::from_notation("0??x0/????/????/????", 4).is_err());
+    }
 }
\ No newline at end of file
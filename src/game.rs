@@ -1,8 +1,10 @@
 use cards::{Cards, NO_WINNER, ILLEGAL_CARDS};
 use player::Player;
+use log::{GameLog, LogEntry};
+use transcript::{Transcript, TranscriptEntry};
 use std::collections::HashSet;
 
-/** 
+/**
     Plays the game with the given list of players until one
     player wins or there is a draw. If a player wins, the
     function returns the number of the player (0 to one less
@@ -10,11 +12,29 @@ use std::collections::HashSet;
     returns -1.
 */
 pub fn play(players: &[usize], player_instances: &mut [Box<Player>]) -> i64 {
+    let (result, _, _) = play_logged(players, player_instances);
+    result
+}
+
+/**
+    Like `play`, but also builds a `GameLog` recording every request and
+    its outcome, suitable for writing out with `GameLog::write_to_file`
+    and later replaying with `replay_log`, and a `Transcript` recording
+    the same turns with the extra analysis fields (`forced`, `result`)
+    `GameLog` doesn't carry, suitable for writing out with
+    `Transcript::write_to_file` and feeding to an external viewer.
+*/
+pub fn play_logged(players: &[usize], player_instances: &mut [Box<Player>]) -> (i64, GameLog, Transcript) {
     let number_of_players = players.len();
     assert!(player_instances.len() <= number_of_players);
 
+    let type_names = players.iter().map(|&p| player_instances[p].info()).collect();
+    let mut log = GameLog::new(number_of_players, type_names);
+    let mut transcript = Transcript::new();
+
     let mut cards = Cards::new(number_of_players);
     let mut history = HashSet::new();
+    let mut turn = 0;
 
     loop {
         for i in 0..number_of_players {
@@ -25,7 +45,9 @@ pub fn play(players: &[usize], player_instances: &mut [Box<Player>]) -> i64 {
             }
             let (other, suit) = player_instances[players[i]].next_move(i, &cards, &history);
             println!("Player {} requests suit {} from player {}", i, suit, other);
-            if player_instances[players[other]].has_card(other, i, suit, &cards, &history) {
+            let (forced, _) = cards.has_card(suit, other, i);
+            let transfer = player_instances[players[other]].has_card(other, i, suit, &cards, &history);
+            if transfer {
                 println!("Player {} hands card {} to player {}", suit, other, i);
                 cards.transfer(suit, other, i, false);
             } else {
@@ -37,17 +59,49 @@ pub fn play(players: &[usize], player_instances: &mut [Box<Player>]) -> i64 {
                 cards.show(usize::max_value());
                 panic!("The cards are in an illegal state. All players lose");
             }
+            log.push(LogEntry::new(i, other, suit, transfer, if winner == NO_WINNER { -1 } else { winner }));
+            let result = player_instances[players[i]].last_evaluated_result();
+            transcript.push(TranscriptEntry::new(turn, i, other, suit, transfer, forced, result));
+            turn += 1;
             if winner != NO_WINNER {
                 cards.show(usize::max_value());
-                return winner
+                log.result = winner;
+                transcript.result = winner;
+                return (winner, log, transcript)
             }
-            let position = cards.position(i);
+            let position = cards.canonical_position(i);
             if history.contains(&position) {
                 cards.show(usize::max_value());
-                return -1
+                log.result = -1;
+                transcript.result = -1;
+                return (-1, log, transcript)
             }
             history.insert(position);
         }
     }
 }
 
+/**
+    Replays a `GameLog` move by move through the same `Cards` transitions
+    that produced it, printing each step. This does not consult any
+    `Player` instances: it simply re-applies the recorded
+    `transfer`/`no_transfer` calls, so it can reproduce a game that was
+    written out by `play_logged` without needing the original players.
+*/
+pub fn replay_log(log: &GameLog) -> i64 {
+    let mut cards = Cards::new(log.number_of_players);
+    for entry in &log.entries {
+        cards.show(entry.player);
+        println!("Player {} requests suit {} from player {}", entry.player, entry.suit, entry.other);
+        if entry.transfer {
+            println!("Player {} hands card {} to player {}", entry.other, entry.suit, entry.player);
+            cards.transfer(entry.suit, entry.other, entry.player, false);
+        } else {
+            println!("Player {} has no cards of suit {}", entry.other, entry.suit);
+            cards.no_transfer(entry.suit, entry.other, entry.player, false);
+        }
+    }
+    cards.show(usize::max_value());
+    log.result
+}
+
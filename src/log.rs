@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{Read, Write};
+
+/**
+    One entry in a GameLog: a single request for a card and its outcome.
+*/
+#[derive(Clone)]
+pub struct LogEntry {
+    pub player: usize,
+    pub other: usize,
+    pub suit: i8,
+    pub transfer: bool,
+    pub winner: i64,
+}
+
+impl LogEntry {
+    pub fn new(player: usize, other: usize, suit: i8, transfer: bool, winner: i64) -> LogEntry {
+        LogEntry { player, other, suit, transfer, winner }
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"player\":{},\"other\":{},\"suit\":{},\"transfer\":{},\"winner\":{}}}",
+            self.player, self.other, self.suit, self.transfer, self.winner)
+    }
+
+    fn from_json(obj: &str) -> Result<LogEntry, String> {
+        let player = _json_field(obj, "player")?.parse::<usize>().map_err(|e| e.to_string())?;
+        let other = _json_field(obj, "other")?.parse::<usize>().map_err(|e| e.to_string())?;
+        let suit = _json_field(obj, "suit")?.parse::<i8>().map_err(|e| e.to_string())?;
+        let transfer = _json_field(obj, "transfer")? == "true";
+        let winner = _json_field(obj, "winner")?.parse::<i64>().map_err(|e| e.to_string())?;
+        Ok(LogEntry { player, other, suit, transfer, winner })
+    }
+}
+
+/**
+    A complete record of a game: how many players took part, what kind
+    of player occupied each seat, and the move-by-move history needed
+    to replay the game through the same `Cards` transitions.
+*/
+pub struct GameLog {
+    pub number_of_players: usize,
+    pub player_types: Vec<String>,
+    pub entries: Vec<LogEntry>,
+    pub result: i64,
+}
+
+impl GameLog {
+    pub fn new(number_of_players: usize, player_types: Vec<String>) -> GameLog {
+        GameLog { number_of_players, player_types, entries: Vec::new(), result: -1 }
+    }
+
+    pub fn push(&mut self, entry: LogEntry) {
+        self.entries.push(entry);
+    }
+
+    /**
+        Serializes the whole game as a single JSON object, with the
+        player count and types at the top level and the move history
+        as an array of entries.
+    */
+    pub fn to_json(&self) -> String {
+        let mut s = String::new();
+        s.push_str("{\"number_of_players\":");
+        s.push_str(&self.number_of_players.to_string());
+        s.push_str(",\"player_types\":[");
+        for (i, t) in self.player_types.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            s.push('"');
+            s.push_str(t);
+            s.push('"');
+        }
+        s.push_str("],\"entries\":[");
+        for (i, e) in self.entries.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            s.push_str(&e.to_json());
+        }
+        s.push_str("],\"result\":");
+        s.push_str(&self.result.to_string());
+        s.push('}');
+        s
+    }
+
+    /**
+        Parses a GameLog from the format written by `to_json`.
+    */
+    pub fn from_json(text: &str) -> Result<GameLog, String> {
+        let number_of_players = _json_field(text, "number_of_players")?
+            .parse::<usize>().map_err(|e| e.to_string())?;
+        let player_types = _json_array(text, "player_types")?
+            .iter().map(|s| s.trim_matches('"').to_string()).collect();
+        let mut log = GameLog::new(number_of_players, player_types);
+        for obj in _json_object_array(text, "entries")? {
+            log.push(LogEntry::from_json(&obj)?);
+        }
+        log.result = _json_field(text, "result")?.parse::<i64>().map_err(|e| e.to_string())?;
+        Ok(log)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_json().as_bytes())
+    }
+
+    pub fn read_from_file(path: &str) -> Result<GameLog, String> {
+        let mut file = File::open(path).map_err(|e| e.to_string())?;
+        let mut text = String::new();
+        file.read_to_string(&mut text).map_err(|e| e.to_string())?;
+        GameLog::from_json(&text)
+    }
+}
+
+/** Finds the raw text of a top-level field, e.g. `"player":3`. */
+pub(crate) fn _json_field(text: &str, name: &str) -> Result<String, String> {
+    let key = format!("\"{}\":", name);
+    let start = text.find(&key).ok_or_else(|| format!("field {} not found", name))? + key.len();
+    let rest = &text[start..];
+    let end = rest.find(|c| c == ',' || c == '}' || c == ']').unwrap_or(rest.len());
+    Ok(rest[..end].trim().to_string())
+}
+
+/**
+    Finds the raw contents of a top-level array field of strings, e.g.
+    `"player_types":["a","b"]`. Splits elements on commas outside of
+    quoted strings, not on every comma, since an element (e.g. a
+    `Player::info()` string) may itself contain commas.
+*/
+pub(crate) fn _json_array(text: &str, name: &str) -> Result<Vec<String>, String> {
+    let key = format!("\"{}\":[", name);
+    let start = text.find(&key).ok_or_else(|| format!("array {} not found", name))? + key.len();
+    let mut elements = vec![];
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut closed = false;
+    for c in text[start..].chars() {
+        if c == '"' {
+            in_string = !in_string;
+        }
+        if c == ']' && !in_string {
+            closed = true;
+            break;
+        }
+        if c == ',' && !in_string {
+            elements.push(current.clone());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    if !closed {
+        return Err(format!("unterminated array {}", name));
+    }
+    if elements.is_empty() && current.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    elements.push(current);
+    Ok(elements)
+}
+
+/** Finds the raw contents of a top-level array of JSON objects, e.g. `"entries":[{...},{...}]`. */
+pub(crate) fn _json_object_array(text: &str, name: &str) -> Result<Vec<String>, String> {
+    let key = format!("\"{}\":[", name);
+    let start = text.find(&key).ok_or_else(|| format!("array {} not found", name))? + key.len();
+    let mut depth = 0;
+    let mut objects = vec![];
+    let mut current = String::new();
+    let mut in_object = false;
+    for c in text[start..].chars() {
+        if c == '{' {
+            depth += 1;
+            in_object = true;
+        }
+        if in_object {
+            current.push(c);
+        }
+        if c == '}' {
+            depth -= 1;
+            if depth == 0 {
+                objects.push(current.clone());
+                current.clear();
+                in_object = false;
+            }
+        }
+        if c == ']' && depth == 0 && !in_object {
+            break;
+        }
+    }
+    Ok(objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json_from_json_round_trip() {
+        let mut log = GameLog::new(2, vec!["RandomPlayer".to_string(), "RandomPlayer".to_string()]);
+        log.push(LogEntry::new(0, 1, 2, true, -1));
+        log.push(LogEntry::new(1, 0, 3, false, 0));
+        log.result = 0;
+
+        let round_tripped = GameLog::from_json(&log.to_json()).unwrap();
+        assert_eq!(round_tripped.number_of_players, log.number_of_players);
+        assert_eq!(round_tripped.player_types, log.player_types);
+        assert_eq!(round_tripped.entries.len(), log.entries.len());
+        assert_eq!(round_tripped.result, log.result);
+    }
+
+    #[test]
+    fn test_to_json_from_json_round_trip_with_commas_in_a_player_type() {
+        // CleverPlayer::info(), EpsilonPlayer::info() and MctsPlayer::info()
+        // all return comma-separated summaries, e.g. "cache size: 5, hits:
+        // 2, misses: 1". A naive split on every comma would chop one
+        // player_types entry into several bogus ones.
+        let player_types = vec![
+            "CleverPlayer: cache size: 5, hits: 2, misses: 1".to_string(),
+            "RandomPlayer".to_string(),
+        ];
+        let log = GameLog::new(2, player_types.clone());
+
+        let round_tripped = GameLog::from_json(&log.to_json()).unwrap();
+        assert_eq!(round_tripped.number_of_players, 2);
+        assert_eq!(round_tripped.player_types, player_types);
+    }
+
+    #[test]
+    fn test_json_array_of_empty_player_types_round_trips() {
+        let log = GameLog::new(0, vec![]);
+        let round_tripped = GameLog::from_json(&log.to_json()).unwrap();
+        assert_eq!(round_tripped.player_types, Vec::<String>::new());
+    }
+}
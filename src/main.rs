@@ -1,10 +1,109 @@
+extern crate rand;
+
 mod cards;
 mod game;
+mod log;
 mod player;
+mod rational;
+mod simulator;
+mod strategy;
+mod transcript;
+mod transposition;
 
-use game::play;
-use player::{HumanPlayer, CleverPlayer, Player};
-use std::{env, process};
+use game::{play_logged, replay_log};
+use log::GameLog;
+use player::{HumanPlayer, CleverPlayer, EpsilonPlayer, MctsPlayer, Player, RandomPlayer, ScriptedPlayer};
+use simulator::{simulate, sweep_prefs};
+use std::{env, fs, process};
+use std::time::Duration;
+
+/** Parses the flattened, comma-separated preference list used by both `prefs:` and `prefs_sweep=`. */
+fn parse_prefs(pv: &[&str]) -> Vec<Vec<usize>> {
+    let len = pv.len();
+    // sensible preference lengths are 3 (three players), 8 (four players), etc
+    let mut prefs_len = 0;
+    for i in 3..10 {
+        if len == i * (i - 2) {
+            prefs_len = i;
+            break;
+        }
+    }
+    if prefs_len == 0 {
+        eprintln!("error -- prefs are not a suitable length (3, 8, 15 etc.)");
+        process::exit(-1);
+    }
+    let part_len = prefs_len - 2;
+    assert!(part_len * prefs_len == pv.len());
+    let mut prefs = Vec::new();
+    let mut src = 0;
+    for _ in 0..prefs_len {
+        let mut part = Vec::new();
+        for _ in 0..part_len {
+            part.push(pv[src].parse::<usize>().unwrap());
+            src += 1;
+        }
+        prefs.push(part);
+    }
+    prefs
+}
+
+/** True if every player's preferences are the same list, rotated by its seat number. */
+fn is_symmetric(prefs: &[Vec<usize>]) -> bool {
+    let prefs_len = prefs.len();
+    let pref0 = &prefs[0];
+    for (i, pref) in prefs.iter().enumerate() {
+        for (p0, &p) in pref0.iter().zip(pref.iter()) {
+            if p != (p0 + i) % prefs_len {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/** Rebuilds one player instance of `kind` (as recorded by `main`'s arg parser) for a `prefs_sweep=` row. */
+fn build_swept_player(
+        kind: &str,
+        seed: u64,
+        epsilon: f64,
+        max_depth: i64,
+        max_has_depth: i64,
+        progress: i64,
+        threads: usize,
+        time_budget: Option<f64>,
+        mcts_iterations: usize,
+        mcts_exploration: f64,
+        mcts_rollout_plies: i64,
+        prefs: Vec<Vec<usize>>,
+        symmetric: bool) -> Box<Player> {
+    if kind == "human" {
+        Box::new(HumanPlayer::new())
+    } else if kind == "random" {
+        Box::new(RandomPlayer::new(seed))
+    } else if kind == "clever" {
+        let mut player = CleverPlayer::new(max_depth, max_has_depth, progress, prefs, symmetric, threads);
+        if let Some(seconds) = time_budget {
+            player = player.with_time_budget(Duration::from_secs_f64(seconds));
+        }
+        Box::new(player)
+    } else if kind == "mcts" {
+        Box::new(MctsPlayer::new(mcts_iterations, mcts_exploration, mcts_rollout_plies, prefs, seed))
+    } else if kind == "epsilon" {
+        let random = RandomPlayer::new(seed);
+        let clever = CleverPlayer::new(max_depth, max_has_depth, progress, prefs, symmetric, threads);
+        Box::new(EpsilonPlayer::new(epsilon, random, clever, seed))
+    } else if let Some(s) = kind.strip_prefix("script:") {
+        let moves = s.split(',').map(|pair| {
+            let mut parts = pair.split(':');
+            let other = parts.next().unwrap().parse::<usize>().unwrap();
+            let suit = parts.next().unwrap().parse::<i8>().unwrap();
+            (other, suit)
+        }).collect();
+        Box::new(ScriptedPlayer::new(moves))
+    } else {
+        panic!("unrecognised player kind recorded for prefs_sweep: {}", kind);
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -12,14 +111,33 @@ fn main() {
 
     let mut player_types : Vec<Box<Player>> = Vec::new();
     let mut players = Vec::new();
+    // Parallel to `player_types`: the kind string used to create each entry,
+    // so `prefs_sweep=` can rebuild a fresh lineup (with new preferences)
+    // without re-parsing the original args.
+    let mut kind_for_type: Vec<String> = Vec::new();
 
     let mut human = None;
     let mut clever = None;
+    let mut random = None;
+    let mut mcts = None;
+    let mut epsilon_player = None;
+    let mut seed = 0u64;
+    let mut epsilon = 0.1;
     let mut max_depth = 1000;
     let mut max_has_depth = 1000;
     let mut progress = 0;
     let mut prefs = Vec::new();
     let mut symmetric = true;
+    let mut json_file = None;
+    let mut transcript_file = None;
+    let mut replay_file = None;
+    let mut simulate_games = None;
+    let mut threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut time_budget = None;
+    let mut mcts_iterations = 1000;
+    let mut mcts_exploration = 2.0f64.sqrt();
+    let mut mcts_rollout_plies = 200;
+    let mut prefs_sweep: Option<Vec<Vec<Vec<usize>>>> = None;
 
     let mut skip_first = true;
     for arg in &args {
@@ -36,6 +154,7 @@ fn main() {
                 let h = player_types.len();
                 human = Some(h);
                 players.push(h);
+                kind_for_type.push("human".to_string());
                 player_types.push(Box::new(HumanPlayer::new()));
             }
         } else if arg == "clever" {
@@ -46,51 +165,102 @@ fn main() {
                 let c = player_types.len();
                 clever = Some(c);
                 players.push(c);
-                player_types.push(Box::new(CleverPlayer::new(max_depth, max_has_depth, progress, prefs.clone(), symmetric)));
-            }
-        } else if arg.starts_with("prefs:") {
-            prefs.clear();
-            let (_, p) = arg.split_at(6);
-            let pv: Vec<&str> = p.split(',').collect();
-            // sensible preference lengths are 3 (three players), 8 (four players), etc
-            let len = pv.len();
-            let mut prefs_len = 0;
-            for i in 3..10 {
-                if len == i * (i - 2) {
-                    prefs_len = i;
-                    break;
+                kind_for_type.push("clever".to_string());
+                let mut player = CleverPlayer::new(max_depth, max_has_depth, progress, prefs.clone(), symmetric, threads);
+                if let Some(seconds) = time_budget {
+                    player = player.with_time_budget(Duration::from_secs_f64(seconds));
                 }
+                player_types.push(Box::new(player));
             }
-            if prefs_len == 0 {
-                eprintln!("error -- prefs are not a suitable length (3, 8, 15 etc.)");
-                process::exit(-1);
+        } else if arg == "random" {
+            if let Some(r) = random {
+                // Already got a random player. Reuse it
+                players.push(r);
+            } else {
+                let r = player_types.len();
+                random = Some(r);
+                players.push(r);
+                kind_for_type.push("random".to_string());
+                player_types.push(Box::new(RandomPlayer::new(seed)));
             }
-            let part_len = prefs_len - 2;
-            assert!(part_len * prefs_len == pv.len());
-            let mut src = 0;
-            for _ in 0..prefs_len {
-                let mut part = Vec::new();
-                for _ in 0..part_len {
-                    // println!("parse pv[{}] = {}", src, pv[src]);
-                    part.push(pv[src].parse::<usize>().unwrap());
-                    src += 1;
-                }
-                prefs.push(part);
+        } else if arg == "mcts" {
+            if let Some(m) = mcts {
+                // Already got an mcts player. Reuse it
+                players.push(m);
+            } else {
+                let m = player_types.len();
+                mcts = Some(m);
+                players.push(m);
+                kind_for_type.push("mcts".to_string());
+                player_types.push(Box::new(MctsPlayer::new(mcts_iterations, mcts_exploration, mcts_rollout_plies, prefs.clone(), seed)));
             }
+        } else if arg == "epsilon" {
+            if let Some(e) = epsilon_player {
+                // Already got an epsilon player. Reuse it
+                players.push(e);
+            } else {
+                let e = player_types.len();
+                epsilon_player = Some(e);
+                players.push(e);
+                kind_for_type.push("epsilon".to_string());
+                let random = RandomPlayer::new(seed);
+                let clever = CleverPlayer::new(max_depth, max_has_depth, progress, prefs.clone(), symmetric, threads);
+                player_types.push(Box::new(EpsilonPlayer::new(epsilon, random, clever, seed)));
+            }
+        } else if arg.starts_with("epsilon=") {
+            let (_, e) = arg.split_at(8);
+            epsilon = e.parse::<f64>().unwrap();
+            println!("epsilon: {}", epsilon);
+        } else if arg.starts_with("mcts_iterations=") {
+            let (_, n) = arg.split_at(16);
+            mcts_iterations = n.parse::<usize>().unwrap();
+            println!("mcts_iterations: {}", mcts_iterations);
+        } else if arg.starts_with("mcts_exploration=") {
+            let (_, c) = arg.split_at(18);
+            mcts_exploration = c.parse::<f64>().unwrap();
+            println!("mcts_exploration: {}", mcts_exploration);
+        } else if arg.starts_with("mcts_rollout_plies=") {
+            let (_, d) = arg.split_at(19);
+            mcts_rollout_plies = d.parse::<i64>().unwrap();
+            println!("mcts_rollout_plies: {}", mcts_rollout_plies);
+        } else if arg.starts_with("seed=") {
+            let (_, s) = arg.split_at(5);
+            seed = s.parse::<u64>().unwrap();
+            println!("seed: {}", seed);
+        } else if arg.starts_with("script:") {
+            let (_, s) = arg.split_at(7);
+            let moves = s.split(',').map(|pair| {
+                let mut parts = pair.split(':');
+                let other = parts.next().unwrap().parse::<usize>().unwrap();
+                let suit = parts.next().unwrap().parse::<i8>().unwrap();
+                (other, suit)
+            }).collect();
+            players.push(player_types.len());
+            kind_for_type.push(arg.clone());
+            player_types.push(Box::new(ScriptedPlayer::new(moves)));
+        } else if arg.starts_with("prefs:") {
+            let (_, p) = arg.split_at(6);
+            let pv: Vec<&str> = p.split(',').collect();
+            prefs = parse_prefs(&pv);
             println!("prefs: {:?}", prefs);
 
-            // are the prefs symmetric?
-            let pref0 = prefs[0].clone();
-            for (i, pref) in prefs.iter().enumerate() {
-                for (p0, &p) in pref0.iter().zip(pref.iter()) {
-                    if symmetric && p != (p0 + i) % prefs_len {
-                        println!("not symmetric");
-                        symmetric = false;
-                        break;
-                    }
-                }
+            if symmetric && !is_symmetric(&prefs) {
+                println!("not symmetric");
+                symmetric = false;
             }
-
+        } else if arg.starts_with("prefs_sweep=") {
+            let (_, f) = arg.split_at(12);
+            let contents = fs::read_to_string(f).unwrap_or_else(|e| {
+                eprintln!("failed to read prefs_sweep file {}: {}", f, e);
+                process::exit(-1);
+            });
+            let rows: Vec<Vec<Vec<usize>>> = contents.lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(|line| parse_prefs(&line.split(',').collect::<Vec<&str>>()))
+                .collect();
+            println!("prefs_sweep: {} rows from {}", rows.len(), f);
+            prefs_sweep = Some(rows);
         } else if arg.starts_with("max_depth=") {
             let (_, d) = arg.split_at(10);
             max_depth = d.parse::<i64>().unwrap();
@@ -99,34 +269,116 @@ fn main() {
             let (_, d) = arg.split_at(14);
             max_has_depth = d.parse::<i64>().unwrap();
             println!("max_has_depth: {}", max_has_depth);
+        } else if arg.starts_with("threads=") {
+            let (_, t) = arg.split_at(8);
+            threads = t.parse::<usize>().unwrap();
+            println!("threads: {}", threads);
+        } else if arg.starts_with("time_budget=") {
+            let (_, t) = arg.split_at(12);
+            let seconds = t.parse::<f64>().unwrap();
+            time_budget = Some(seconds);
+            println!("time_budget: {} seconds", seconds);
         } else if arg.starts_with("progress=") {
             let (_, d) = arg.split_at(9);
             progress = d.parse::<i64>().unwrap();
             println!("progress: {}", progress);
+        } else if arg.starts_with("json=") {
+            let (_, f) = arg.split_at(5);
+            json_file = Some(f.to_string());
+        } else if arg.starts_with("transcript=") {
+            let (_, f) = arg.split_at(11);
+            transcript_file = Some(f.to_string());
+        } else if arg.starts_with("replay=") {
+            let (_, f) = arg.split_at(7);
+            replay_file = Some(f.to_string());
+        } else if arg.starts_with("simulate=") {
+            let (_, n) = arg.split_at(9);
+            simulate_games = Some(n.parse::<usize>().unwrap());
         } else if arg == "help" {
             println!("{} [options] [human|clever]*", args[0]);
             println!("e.g. {} max_depth=3 prefs=1,2,0 human human clever", args[0]);
             println!("Options:");
+            println!("    random                a player that picks uniformly among legal moves");
+            println!("    mcts                  a Monte-Carlo tree search player, for large player counts");
+            println!("    mcts_iterations=<int> playouts per move for 'mcts' (1000)");
+            println!("    mcts_exploration=<f>  UCT exploration constant 'c' for 'mcts' (sqrt(2))");
+            println!("    mcts_rollout_plies=<int>  rollout ply limit before scoring a draw for 'mcts' (200)");
+            println!("    epsilon               a player that defers to 'random' with probability epsilon, else 'clever'");
+            println!("    epsilon=<f64>         probability 'epsilon' defers to random play (0.1)");
+            println!("    seed=<u64>            seed for the next 'random', 'mcts' or 'epsilon' player (0)");
+            println!("    script:<o>:<s>,...    a player that replays this fixed (other, suit) list");
             println!("    max_depth=<int>       how deep to search (1000)");
             println!("    max_has_depth=<int>   how deep to search for 'has_card' (1000)");
             println!("    progress=<int>        show progress every N cache writes (0)");
+            println!("    threads=<int>         worker threads for clever's root search (available parallelism)");
+            println!("    time_budget=<secs>    iterative-deepening time budget for clever, instead of max_depth");
             println!("    prefs:<int>,<int>,... 2nd, 3rd preferences for each player (none)");
+            println!("    json=<file>           write the completed game out as a JSON log");
+            println!("    transcript=<file>     write a per-turn JSON analysis transcript");
+            println!("    replay=<file>         replay a JSON game log instead of playing");
+            println!("    simulate=<int>        run this many games and report aggregate stats");
+            println!("    prefs_sweep=<file>    with simulate=, re-run for each prefs row (one 'prefs:'-style list per line) in <file>, printing a table per row");
         } else {
             eprintln!("unrecognised arg {}: try {} help", arg, args[0]);
             process::exit(-1);
         }
     }
 
+    if let Some(f) = replay_file {
+        let log = GameLog::read_from_file(&f).unwrap_or_else(|e| {
+            eprintln!("failed to read replay file {}: {}", f, e);
+            process::exit(-1);
+        });
+        let result = replay_log(&log);
+        if result == -1 {
+            println!("Result is a draw");
+        } else {
+            println!("Win for player {}", result);
+        }
+        return;
+    }
+
     if players.len() < 2 {
         eprintln!("need at least two players: try {} help", args[0]);
         process::exit(-1);
     }
 
+    if let Some(games) = simulate_games {
+        if let Some(rows) = prefs_sweep {
+            sweep_prefs(&rows, games, |row_prefs| {
+                let row_symmetric = is_symmetric(row_prefs);
+                let row_player_types: Vec<Box<Player>> = kind_for_type.iter().map(|kind| {
+                    build_swept_player(
+                        kind, seed, epsilon, max_depth, max_has_depth, progress, threads,
+                        time_budget, mcts_iterations, mcts_exploration, mcts_rollout_plies,
+                        row_prefs.to_vec(), row_symmetric)
+                }).collect();
+                (players.clone(), row_player_types)
+            });
+            return;
+        }
+        let result = simulate(&players, player_types.as_mut_slice(), games);
+        result.print_table();
+        return;
+    }
+
     // run the game if we can
-    let result = play(&players, player_types.as_mut_slice());
+    let (result, log, transcript) = play_logged(&players, player_types.as_mut_slice());
     if result == -1 {
         println!("Result is a draw");
     } else {
         println!("Win for player {}", result);
     }
+
+    if let Some(f) = json_file {
+        if let Err(e) = log.write_to_file(&f) {
+            eprintln!("failed to write json log to {}: {}", f, e);
+        }
+    }
+
+    if let Some(f) = transcript_file {
+        if let Err(e) = transcript.write_to_file(&f) {
+            eprintln!("failed to write json transcript to {}: {}", f, e);
+        }
+    }
 }
@@ -1,9 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::io;
 use std::io::BufRead;
 use std::io::Write;
 use std::cmp::min;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use cards::{Cards, ILLEGAL_CARDS, NO_WINNER};
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use transposition::TranspositionTable;
 
 /** 
     Interface that defines how players interact
@@ -23,6 +31,18 @@ pub trait Player {
         Returns information about this object
      */
     fn info(&self) -> String;
+
+    /**
+        The engine's evaluated outcome for the move this player just
+        returned from `next_move` -- the predicted winning player, or
+        -1 for an unresolved/drawish position. Used only to enrich
+        `Transcript`'s per-turn record. `CleverPlayer` reports it, and
+        `EpsilonPlayer` does when its move came from its `CleverPlayer`;
+        every other player, `MctsPlayer` included, keeps the default `None`.
+    */
+    fn last_evaluated_result(&self) -> Option<i64> {
+        None
+    }
 }
 
 /** 
@@ -87,22 +107,34 @@ impl Player for HumanPlayer {
     }
 }
 
-/** 
+/** Default capacity of a `CleverPlayer`'s transposition table, shared across all its shards. */
+const TRANSPOSITION_CAPACITY: usize = 1 << 20;
+
+/**
     Implementation of Player that looks ahead, playing the best move
-    available.
+    available. The search is read-only once a node's candidate moves
+    have been enumerated, so `CleverPlayer`'s internal state (the
+    transposition cache and the progress counter) uses interior
+    mutability: this lets the root-level search in `next_move` fan the
+    candidate moves for this ply out across a pool of worker threads,
+    each recursing sequentially from there with shared access to the
+    same cache.
 */
 pub struct CleverPlayer {
     max_depth: i64,
     max_has_depth: i64,
     progress: i64,
-    current_progress: i64,
+    current_progress: AtomicI64,
     preferences: Vec<Vec<usize>>,
     symmetric: bool,
-    _cached_moves: HashMap<i128, (i8, i8, i8)>,
+    threads: usize,
+    time_budget: Option<Duration>,
+    last_result: Option<i64>,
+    _transposition: TranspositionTable<(i8, i8, i8)>,
 }
 
 impl CleverPlayer {
-    /** 
+    /**
         The max_depth specifies how far ahead the player will look
         before making a move. For example, zero means only consider
         the immediate move, so don't play into an immediate lose.
@@ -111,76 +143,102 @@ impl CleverPlayer {
         before saying whether they have a card. For example, zero means
         only worry about the immediate effect.
 
-        If preferences is specified, it states who the each of the 
+        If preferences is specified, it states who the each of the
         players wants to win. It is a list of lists of player numbers.
 
-        If other_player is supplied, we share its cache.
+        threads controls how many worker threads explore the root's
+        candidate moves in parallel; pass 1 to force single-threaded,
+        deterministic-order search (useful for debugging).
     */
     pub fn new(
-        max_depth: i64, 
+        max_depth: i64,
         max_has_depth: i64,
-        progress: i64, 
-        preferences: Vec<Vec<usize>>, 
-        symmetric: bool) -> CleverPlayer {
+        progress: i64,
+        preferences: Vec<Vec<usize>>,
+        symmetric: bool,
+        threads: usize) -> CleverPlayer {
 
         CleverPlayer {
             max_depth: max_depth,
             max_has_depth: max_has_depth,
             progress: progress,
-            current_progress: 0,
+            current_progress: AtomicI64::new(0),
             preferences: preferences,
             symmetric: symmetric,
-            _cached_moves: HashMap::new(),
+            threads: threads.max(1),
+            time_budget: None,
+            last_result: None,
+            _transposition: TranspositionTable::new(TRANSPOSITION_CAPACITY),
         }
     }
 
-    /** 
-        Like next_move, but it also returns a result, which says what 
+    /**
+        Switches `next_move` from a fixed `max_depth` to iterative
+        deepening: it first resolves depth 0 with no deadline, then
+        searches depth 1, 2, 3, ... re-using the shared transposition
+        table for move ordering between iterations, and returns the best
+        move from the deepest iteration that finished within
+        `time_budget`, wall-clock, of when the search started (the
+        depth-0 result if no deeper iteration did).
+    */
+    pub fn with_time_budget(mut self, time_budget: Duration) -> CleverPlayer {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
+    fn _note_progress(&self) {
+        if self.progress <= 0 {
+            return;
+        }
+        let previous = self.current_progress.fetch_add(1, Ordering::Relaxed);
+        if previous + 1 >= self.progress {
+            self.current_progress.store(0, Ordering::Relaxed);
+            print!(".");
+            io::stdout().flush().unwrap();
+        }
+    }
+
+    /**
+        Like next_move, but it also returns a result, which says what
         the final best-case result is as a result of this move.
 
         Returns a tuple of (other_player, suit, result, draw_position)
     */
-    pub fn _evaluate_move(&mut self, this: usize, cards: &Cards, history: & HashSet<i128>, depth: i64)
+    pub fn _evaluate_move(&self, this: usize, cards: &Cards, history: & HashSet<i128>, depth: i64)
             -> (usize, i8, i64, i128) {
-        let permutation = cards.permutation(this);
+        let permutation = cards.canonical_permutation(this);
         let pos = cards.position_given_permutation(&permutation, this, self.symmetric);
+        let quick_hash = cards.zobrist_hash();
         let n = permutation.len();
-        match self._cached_moves.get(&pos) {
-            Some(&(other_c, suit_c, result_c)) => {
+        match self._transposition.get(quick_hash, pos) {
+            Some((other_c, suit_c, result_c)) => {
                 let other = (other_c as usize + this) % n;
                 let result = if result_c < 0 { result_c as i64 } else { ((result_c as usize + this) % n) as i64 };
                 let suit = permutation[suit_c as usize];
                 return (other, suit, result, -1)
             }
             None => {
-                let (other, suit, result, draw_position) 
+                let (other, suit, result, draw_position)
                     = self._evaluate_move_uncached(this, cards, history, depth, &permutation);
 
-                if self.progress > 0 {
-                    self.current_progress += 1;
-                    if self.current_progress == self.progress {
-                        self.current_progress = 0;
-                        print!(".");
-                        io::stdout().flush().unwrap();
-                    }
-                }
+                self._note_progress();
                 let other_c = (n + other - this) % n;
                 let result_c = if result < 0 { result } else { ((n + result as usize - this) % n) as i64 };
                 let found = permutation.iter().position(|&x| x == suit);
                 let suit_c = found.unwrap();
                 if result_c >= 0 || !history.contains(&draw_position) {
-                    self._cached_moves.insert(pos, (other_c as i8, suit_c as i8, result_c as i8));
+                    self._transposition.insert(quick_hash, pos, (other_c as i8, suit_c as i8, result_c as i8));
                 }
                 return (other, suit, result, draw_position);
-    
+
             }
         }
     }
 
-    /** 
+    /**
         Like _evaluate_move, but not using the cache.
     */
-    pub fn _evaluate_move_uncached(&mut self, this: usize, cards: &Cards, history: & HashSet<i128>, depth: i64, permutation: &[i8]) 
+    pub fn _evaluate_move_uncached(&self, this: usize, cards: &Cards, history: & HashSet<i128>, depth: i64, permutation: &[i8])
             -> (usize, i8, i64, i128) {
         let mut other_winners = vec![];
         let legal_moves = cards.legal_moves_given_permutation(this, permutation);
@@ -237,7 +295,7 @@ impl CleverPlayer {
             let mut copy_history = history.clone();
             copy_history.insert(position);
             let (_, _, next_winner, draw_position)
-                = self._evaluate_move(next_player, &copy_cards, &mut copy_history, depth - 1);
+                = self._evaluate_move(next_player, &copy_cards, &copy_history, depth - 1);
             if next_winner == this as i64 {
                 return (other, suit, next_winner, -1);
             }
@@ -274,7 +332,7 @@ impl CleverPlayer {
         panic!("should never get here")
     }
 
-    fn _evaluate_has_card(&mut self, this: usize, other: usize, suit: i8, cards: &Cards, history: & HashSet<i128>, given_depth: i64) -> bool {
+    fn _evaluate_has_card(&self, this: usize, other: usize, suit: i8, cards: &Cards, history: & HashSet<i128>, given_depth: i64) -> bool {
         let (forced, has) = cards.has_card(suit, this, other);
         if forced {
             return has;
@@ -286,7 +344,10 @@ impl CleverPlayer {
             return true;
         }
 
-        let depth = min(given_depth, self.max_has_depth);
+        // `given_depth` can arrive negative (callers pass `depth - 1` and `depth` can be
+        // 0, e.g. the mandatory depth-0 iteration in `_evaluate_move_timed`). Clamp it to
+        // 0 here so it still bottoms out instead of recursing forever through negative depths.
+        let depth = min(given_depth, self.max_has_depth).max(0);
         if depth == 0 {
             return yes_winner != NO_WINNER;
         }
@@ -356,13 +417,381 @@ impl CleverPlayer {
         }
         return false;
     }
+
+    /**
+        The outcome of resolving one root candidate move far enough to
+        classify it the same way `_evaluate_move_uncached`'s loop body
+        would. Computed by a worker thread in `_evaluate_move_root`;
+        the classification is then reduced sequentially, in the
+        original move order, so the result is identical to the
+        single-threaded search regardless of how the threads interleave.
+    */
+    fn _evaluate_root_candidate(&self, this: usize, cards: &Cards, history: &HashSet<i128>, depth: i64, other: usize, suit: i8) -> RootOutcome {
+        let mut copy_cards = cards.clone();
+        let has = self._evaluate_has_card(other, this, suit, &copy_cards, history, depth - 1);
+        if has {
+            copy_cards.transfer(suit, other, this, false);
+        } else {
+            copy_cards.no_transfer(suit, other, this, false);
+        }
+        let winner = copy_cards.test_winner(this);
+        if winner == ILLEGAL_CARDS {
+            return RootOutcome::Illegal;
+        }
+        if winner == this as i64 {
+            return RootOutcome::SelfWin(winner);
+        }
+        if winner != NO_WINNER {
+            return RootOutcome::OtherWin(winner);
+        }
+        if depth == 0 {
+            return RootOutcome::OutOfDepth;
+        }
+        let next_player = copy_cards.next_player(this);
+        let position = copy_cards.position(next_player);
+        if history.contains(&position) {
+            return RootOutcome::Draw(position);
+        }
+        let mut copy_history = history.clone();
+        copy_history.insert(position);
+        let (_, _, next_winner, draw_position) = self._evaluate_move(next_player, &copy_cards, &copy_history, depth - 1);
+        RootOutcome::Continue(next_winner, draw_position)
+    }
+
+    /**
+        Like `_evaluate_move_uncached`, but the root's candidate moves
+        are resolved across a pool of `self.threads` worker threads
+        instead of one at a time: each thread pulls the next
+        unclaimed move off a shared atomic counter (a simple
+        work-stealing queue) and resolves it using the shared,
+        lock-sharded transposition cache. Once every move has been
+        resolved, the results are reduced sequentially in their
+        original order, which reproduces exactly the tie-break rules
+        of the single-threaded search.
+    */
+    pub fn _evaluate_move_root(&self, this: usize, cards: &Cards, history: &HashSet<i128>, depth: i64) -> (usize, i8, i64, i128) {
+        let permutation = cards.canonical_permutation(this);
+        let legal_moves = cards.legal_moves_given_permutation(this, &permutation);
+        assert!(legal_moves.len() > 0);
+
+        if self.threads <= 1 || legal_moves.len() == 1 {
+            return self._evaluate_move_uncached(this, cards, history, depth, &permutation);
+        }
+
+        let results: Vec<Mutex<Option<RootOutcome>>> = legal_moves.iter().map(|_| Mutex::new(None)).collect();
+        let next_index = AtomicUsize::new(0);
+        let num_threads = self.threads.min(legal_moves.len());
+
+        thread::scope(|scope| {
+            for _ in 0..num_threads {
+                scope.spawn(|| {
+                    loop {
+                        let i = next_index.fetch_add(1, Ordering::Relaxed);
+                        if i >= legal_moves.len() {
+                            break;
+                        }
+                        let (other, suit) = legal_moves[i];
+                        let outcome = self._evaluate_root_candidate(this, cards, history, depth, other, suit);
+                        *results[i].lock().unwrap() = Some(outcome);
+                    }
+                });
+            }
+        });
+
+        let mut other_winners = if !self.preferences.is_empty() {
+            vec![None; self.preferences[this].len()]
+        } else {
+            vec![]
+        };
+        let mut draw = None;
+        let mut out_of_depth = None;
+        let mut lose = None;
+        let mut immediate_lose = None;
+
+        for (i, &(other, suit)) in legal_moves.iter().enumerate() {
+            let outcome = results[i].lock().unwrap().take().unwrap();
+            match outcome {
+                RootOutcome::Illegal => {
+                    println!("WARNING: illegal cards after move suit={} other={} this={}", suit, other, this);
+                    continue;
+                }
+                RootOutcome::SelfWin(winner) => {
+                    return (other, suit, winner, -1);
+                }
+                RootOutcome::OtherWin(winner) => {
+                    if !self.preferences.is_empty() {
+                        let p = &self.preferences[this];
+                        if let Some(f) = p.iter().position(|&x| x == winner as usize) {
+                            other_winners[f] = Some((other, suit, winner, -1));
+                        } else {
+                            immediate_lose = Some((other, suit, winner, -1));
+                        }
+                    } else {
+                        immediate_lose = Some((other, suit, winner, -1));
+                    }
+                }
+                RootOutcome::OutOfDepth => {
+                    out_of_depth = Some((other, suit, -1, -1));
+                }
+                RootOutcome::Draw(position) => {
+                    draw = Some((other, suit, -1, position));
+                }
+                RootOutcome::Continue(next_winner, draw_position) => {
+                    if next_winner == this as i64 {
+                        return (other, suit, next_winner, -1);
+                    }
+                    if next_winner < 0 {
+                        draw = Some((other, suit, -1, draw_position));
+                    } else if !self.preferences.is_empty() {
+                        let p = &self.preferences[this];
+                        if let Some(f) = p.iter().position(|&x| x == next_winner as usize) {
+                            other_winners[f] = Some((other, suit, next_winner, 0));
+                        } else {
+                            lose = Some((other, suit, next_winner, -1));
+                        }
+                    } else {
+                        lose = Some((other, suit, next_winner, -1));
+                    }
+                }
+            }
+        }
+        if let Some(result) = draw {
+            return result;
+        }
+        if let Some(result) = out_of_depth {
+            return result;
+        }
+        for other_winner in other_winners {
+            if let Some(result) = other_winner {
+                return result;
+            }
+        }
+        if let Some(result) = lose {
+            return result;
+        }
+        if let Some(result) = immediate_lose {
+            return result;
+        }
+        panic!("should never get here")
+    }
+
+    /**
+        Like `_evaluate_move_root`, but always resolves the root's
+        candidate moves sequentially (regardless of `self.threads`) so
+        that `deadline` can be checked between them, returning `None`
+        rather than a partial result once time is up. This is the
+        per-iteration search `_evaluate_move_timed`'s driver calls; it
+        shares `_evaluate_root_candidate` with the threaded root search,
+        so a sequential and a parallel ply reach the same classification
+        for each move, only the ability to bail out early differs.
+    */
+    fn _evaluate_move_root_timed(&self, this: usize, cards: &Cards, history: &HashSet<i128>, depth: i64, deadline: Option<Instant>) -> Option<(usize, i8, i64, i128)> {
+        let permutation = cards.canonical_permutation(this);
+        let legal_moves = cards.legal_moves_given_permutation(this, &permutation);
+        assert!(legal_moves.len() > 0);
+
+        let mut other_winners = if !self.preferences.is_empty() {
+            vec![None; self.preferences[this].len()]
+        } else {
+            vec![]
+        };
+        let mut draw = None;
+        let mut out_of_depth = None;
+        let mut lose = None;
+        let mut immediate_lose = None;
+
+        for &(other, suit) in &legal_moves {
+            if let Some(dl) = deadline {
+                if Instant::now() >= dl {
+                    return None;
+                }
+            }
+            let outcome = self._evaluate_root_candidate(this, cards, history, depth, other, suit);
+            match outcome {
+                RootOutcome::Illegal => {
+                    println!("WARNING: illegal cards after move suit={} other={} this={}", suit, other, this);
+                    continue;
+                }
+                RootOutcome::SelfWin(winner) => {
+                    return Some((other, suit, winner, -1));
+                }
+                RootOutcome::OtherWin(winner) => {
+                    if !self.preferences.is_empty() {
+                        let p = &self.preferences[this];
+                        if let Some(f) = p.iter().position(|&x| x == winner as usize) {
+                            other_winners[f] = Some((other, suit, winner, -1));
+                        } else {
+                            immediate_lose = Some((other, suit, winner, -1));
+                        }
+                    } else {
+                        immediate_lose = Some((other, suit, winner, -1));
+                    }
+                }
+                RootOutcome::OutOfDepth => {
+                    out_of_depth = Some((other, suit, -1, -1));
+                }
+                RootOutcome::Draw(position) => {
+                    draw = Some((other, suit, -1, position));
+                }
+                RootOutcome::Continue(next_winner, draw_position) => {
+                    if next_winner == this as i64 {
+                        return Some((other, suit, next_winner, -1));
+                    }
+                    if next_winner < 0 {
+                        draw = Some((other, suit, -1, draw_position));
+                    } else if !self.preferences.is_empty() {
+                        let p = &self.preferences[this];
+                        if let Some(f) = p.iter().position(|&x| x == next_winner as usize) {
+                            other_winners[f] = Some((other, suit, next_winner, 0));
+                        } else {
+                            lose = Some((other, suit, next_winner, -1));
+                        }
+                    } else {
+                        lose = Some((other, suit, next_winner, -1));
+                    }
+                }
+            }
+        }
+        if let Some(result) = draw {
+            return Some(result);
+        }
+        if let Some(result) = out_of_depth {
+            return Some(result);
+        }
+        for other_winner in other_winners {
+            if let Some(result) = other_winner {
+                return Some(result);
+            }
+        }
+        if let Some(result) = lose {
+            return Some(result);
+        }
+        if let Some(result) = immediate_lose {
+            return Some(result);
+        }
+        panic!("should never get here")
+    }
+
+    /**
+        The iterative-deepening driver used by `next_move` once
+        `time_budget` is set: first resolves the root at depth 0 with no
+        deadline, so that iteration always completes and there is always
+        a committed result, then at depth 1, 2, 3, ...,
+        each time re-using the shared transposition table built up by
+        the shallower iterations, and keeps the best move from the
+        deepest iteration that finished before the budget ran out. A
+        deeper iteration that gets cut short by `_evaluate_move_root_timed`
+        returning `None` is discarded outright, so it can never
+        overwrite the previous, committed result.
+    */
+    pub fn _evaluate_move_timed(&self, this: usize, cards: &Cards, history: &HashSet<i128>) -> (usize, i8, i64, i128) {
+        let deadline = self.time_budget.map(|budget| Instant::now() + budget);
+        let mut best = self._evaluate_move_root_timed(this, cards, history, 0, None)
+            .expect("a depth-0 iteration has no recursion to abort and must always complete");
+        let mut depth = 1;
+        loop {
+            if let Some(dl) = deadline {
+                if Instant::now() >= dl {
+                    break;
+                }
+            }
+            match self._evaluate_move_root_timed(this, cards, history, depth, deadline) {
+                Some(result) => best = result,
+                None => break,
+            }
+            depth += 1;
+        }
+        best
+    }
+
+    /**
+        Reconstructs the principal variation -- the forced line of play
+        the search expects from this position -- as a vector of
+        `(player, other, suit, has_answer)` steps. The transposition
+        cache stores only one successor move per position (to bound its
+        memory, as `_evaluate_move` already does for plain move lookup),
+        not a full line, so this walks that chain of successors one ply
+        at a time: look up the cached move for the current position (or
+        resolve it on demand with `_evaluate_move` if it isn't cached
+        yet), re-derive the `has_card` answer the same way the search
+        did, and apply it to advance to the next position. Stops once a
+        ply wins, the position repeats (a draw), or `max_depth` + 1
+        plies have been walked. Panics if a
+        reconstructed move leaves the cards in an illegal state, since
+        that means the cached move disagrees with the game's actual
+        rules rather than the line having reached a normal resolution.
+    */
+    pub fn best_line(&self, this: usize, cards: &Cards, history: &HashSet<i128>) -> Vec<(usize, usize, i8, bool)> {
+        let mut line = vec![];
+        let mut this = this;
+        let mut cards = cards.clone();
+        let mut history = history.clone();
+        let mut depth = self.max_depth;
+
+        loop {
+            let permutation = cards.canonical_permutation(this);
+            let pos = cards.position_given_permutation(&permutation, this, self.symmetric);
+            let quick_hash = cards.zobrist_hash();
+            let n = permutation.len();
+            let (other, suit) = match self._transposition.get(quick_hash, pos) {
+                Some((other_c, suit_c, _)) => ((other_c as usize + this) % n, permutation[suit_c as usize]),
+                None => {
+                    let (other, suit, _, _) = self._evaluate_move(this, &cards, &history, depth);
+                    (other, suit)
+                }
+            };
+            let has = self._evaluate_has_card(other, this, suit, &cards, &history, depth - 1);
+            line.push((this, other, suit, has));
+            if has {
+                cards.transfer(suit, other, this, false);
+            } else {
+                cards.no_transfer(suit, other, this, false);
+            }
+
+            let winner = cards.test_winner(this);
+            if winner == ILLEGAL_CARDS {
+                cards.show(usize::max_value());
+                panic!("best_line reconstructed a move that leaves the cards in an illegal state -- \
+                    the cached move disagrees with what the game rules actually allow");
+            }
+            if winner != NO_WINNER {
+                break;
+            }
+            let next_player = cards.next_player(this);
+            let position = cards.position(next_player);
+            if history.contains(&position) {
+                break;
+            }
+            history.insert(position);
+            this = next_player;
+            depth -= 1;
+            if depth < 0 {
+                break;
+            }
+        }
+        line
+    }
+}
+
+/** The classification of one root candidate move, produced by a worker thread. */
+enum RootOutcome {
+    Illegal,
+    SelfWin(i64),
+    OtherWin(i64),
+    OutOfDepth,
+    Draw(i128),
+    Continue(i64, i128),
 }
 
 impl Player for CleverPlayer {
     fn next_move(&mut self, this: usize, cards: &Cards, history: & HashSet<i128>) -> (usize, i8) {
-        let max_depth = self.max_depth;
-        let (other, suit, result, _) = self._evaluate_move(this, cards, history, max_depth);
+        let (other, suit, result, _) = if self.time_budget.is_some() {
+            self._evaluate_move_timed(this, cards, history)
+        } else {
+            self._evaluate_move_root(this, cards, history, self.max_depth)
+        };
         println!("Result={}", result);
+        self.last_result = Some(result);
         return (other, suit);
     }
 
@@ -372,14 +801,456 @@ impl Player for CleverPlayer {
     }
 
     fn info(&self) -> String {
-        let len = self._cached_moves.len();
-        return format!("cache size: {}", len);
+        let (hits, misses) = self._transposition.stats();
+        return format!("cache size: {}, hits: {}, misses: {}", self._transposition.len(), hits, misses);
+    }
+
+    fn last_evaluated_result(&self) -> Option<i64> {
+        self.last_result
+    }
+}
+
+/**
+    Implementation of Player that picks uniformly at random among the
+    legal moves available, using a seeded RNG so games are reproducible.
+*/
+pub struct RandomPlayer {
+    rng: StdRng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> RandomPlayer {
+        RandomPlayer { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn next_move(&mut self, this: usize, cards: &Cards, _history: &HashSet<i128>) -> (usize, i8) {
+        let n = cards.config.number_of_suits;
+        let permutation: Vec<i8> = (0..n).collect();
+        let moves = cards.legal_moves_given_permutation(this, &permutation);
+        assert!(!moves.is_empty());
+        let i = self.rng.gen_range(0..moves.len());
+        moves[i]
+    }
+
+    fn has_card(&mut self, this: usize, other: usize, suit: i8, cards: &Cards, _history: &HashSet<i128>) -> bool {
+        let (forced, has) = cards.has_card(suit, this, other);
+        if forced {
+            return has;
+        }
+        self.rng.gen_bool(0.5)
+    }
+
+    fn info(&self) -> String {
+        "random".to_string()
+    }
+}
+
+/**
+    Implementation of Player that blends a `RandomPlayer` and a
+    `CleverPlayer`: with probability `epsilon` it defers each decision
+    to the `RandomPlayer`, and otherwise to the `CleverPlayer`. This
+    gives a noisy, partially-adversarial opponent for stress-testing
+    `CleverPlayer` or for `simulator::simulate`'s win/draw frequencies,
+    at a dial-able distance from fully deterministic play.
+*/
+pub struct EpsilonPlayer {
+    epsilon: f64,
+    random: RandomPlayer,
+    clever: CleverPlayer,
+    rng: StdRng,
+    last_next_move_was_random: bool,
+}
+
+impl EpsilonPlayer {
+    pub fn new(epsilon: f64, random: RandomPlayer, clever: CleverPlayer, seed: u64) -> EpsilonPlayer {
+        EpsilonPlayer { epsilon, random, clever, rng: StdRng::seed_from_u64(seed), last_next_move_was_random: false }
+    }
+
+    fn _defer_to_random(&mut self) -> bool {
+        self.rng.gen_bool(self.epsilon)
+    }
+}
+
+impl Player for EpsilonPlayer {
+    fn next_move(&mut self, this: usize, cards: &Cards, history: &HashSet<i128>) -> (usize, i8) {
+        self.last_next_move_was_random = self._defer_to_random();
+        if self.last_next_move_was_random {
+            self.random.next_move(this, cards, history)
+        } else {
+            self.clever.next_move(this, cards, history)
+        }
+    }
+
+    fn has_card(&mut self, this: usize, other: usize, suit: i8, cards: &Cards, history: &HashSet<i128>) -> bool {
+        // Deliberately does not touch `last_next_move_was_random`: that field
+        // tracks the outcome of this *seat's* next_move, and the same
+        // EpsilonPlayer instance can be asked to answer has_card on
+        // behalf of a different seat in between (the CLI's "epsilon
+        // epsilon" idiom shares one instance across two seats), which
+        // would otherwise clobber the flag before last_evaluated_result
+        // is read for the seat that actually called next_move.
+        if self._defer_to_random() {
+            self.random.has_card(this, other, suit, cards, history)
+        } else {
+            self.clever.has_card(this, other, suit, cards, history)
+        }
+    }
+
+    fn info(&self) -> String {
+        format!("epsilon: {}, clever: [{}]", self.epsilon, self.clever.info())
+    }
+
+    fn last_evaluated_result(&self) -> Option<i64> {
+        if self.last_next_move_was_random {
+            None
+        } else {
+            self.clever.last_evaluated_result()
+        }
+    }
+}
+
+/**
+    Implementation of Player that replays a fixed list of moves, useful
+    for reproducing a logged game or for scripted test fixtures. Once
+    the list is exhausted, it falls back to asking the first legal move.
+*/
+pub struct ScriptedPlayer {
+    moves: Vec<(usize, i8)>,
+    next: usize,
+}
+
+impl ScriptedPlayer {
+    pub fn new(moves: Vec<(usize, i8)>) -> ScriptedPlayer {
+        ScriptedPlayer { moves, next: 0 }
+    }
+}
+
+impl Player for ScriptedPlayer {
+    fn next_move(&mut self, this: usize, cards: &Cards, _history: &HashSet<i128>) -> (usize, i8) {
+        if self.next < self.moves.len() {
+            let result = self.moves[self.next];
+            self.next += 1;
+            return result;
+        }
+        let n = cards.config.number_of_suits;
+        let permutation: Vec<i8> = (0..n).collect();
+        let moves = cards.legal_moves_given_permutation(this, &permutation);
+        assert!(!moves.is_empty(), "ScriptedPlayer ran out of moves and has no legal fallback");
+        moves[0]
+    }
+
+    fn has_card(&mut self, this: usize, other: usize, suit: i8, cards: &Cards, _history: &HashSet<i128>) -> bool {
+        let (forced, has) = cards.has_card(suit, this, other);
+        if forced {
+            return has;
+        }
+        true
+    }
+
+    fn info(&self) -> String {
+        "scripted".to_string()
+    }
+}
+
+/**
+    One node of the search tree built by `MctsPlayer`: the `Cards`
+    position reached after `mover` chose the move that led here (`None`
+    at the root, which is the position `next_move` was asked about),
+    the moves not yet expanded from here, and the UCT running totals.
+    Children are referenced by index into `MctsPlayer::_search`'s flat
+    arena rather than owned inline, since Rust's borrow checker can't
+    walk a recursively owned tree by mutable reference while descending
+    and then backing out again.
+*/
+struct MctsNode {
+    this: usize,
+    mover: Option<usize>,
+    mover_move: Option<(usize, i8)>,
+    cards: Cards,
+    history: HashSet<i128>,
+    /** The winner (or -1 for a draw) if the move that led here already ended the game, so there is nothing left to expand or roll out. */
+    terminal: Option<i64>,
+    untried: Vec<(usize, i8)>,
+    children: Vec<usize>,
+    parent: Option<usize>,
+    visits: u64,
+    wins: f64,
+}
+
+impl MctsNode {
+    fn new(this: usize, mover: Option<usize>, mover_move: Option<(usize, i8)>, cards: Cards, history: HashSet<i128>, parent: Option<usize>, terminal: Option<i64>) -> MctsNode {
+        let untried = if terminal.is_some() {
+            vec![]
+        } else {
+            let permutation: Vec<i8> = (0..cards.config.number_of_suits).collect();
+            cards.legal_moves_given_permutation(this, &permutation)
+        };
+        MctsNode { this, mover, mover_move, cards, history, terminal, untried, children: vec![], parent, visits: 0, wins: 0.0 }
+    }
+
+    fn is_fully_expanded(&self) -> bool {
+        self.untried.is_empty()
+    }
+}
+
+/**
+    Implementation of Player that uses Monte-Carlo Tree Search (UCT)
+    instead of exhaustive minimax, so it stays usable at player counts
+    where `CleverPlayer` blows up (`test_five_clever_biased_players` is
+    `#[ignore]`d for exactly this reason). Each call to `next_move`
+    grows a fresh tree of `iterations` playouts rooted at the current
+    position: select down to a node with an untried move using the UCT
+    formula, expand one child, roll it out to a terminal result (or a
+    ply-limited cutoff) with random legal moves, and back-propagate a
+    reward up the path. The move returned is the root's most-visited
+    child, which converges on the best move as `iterations` grows
+    without needing the position to be solved exactly.
+*/
+pub struct MctsPlayer {
+    iterations: usize,
+    exploration: f64,
+    max_rollout_plies: i64,
+    preferences: Vec<Vec<usize>>,
+    rng: StdRng,
+}
+
+impl MctsPlayer {
+    /**
+        `iterations` is how many select/expand/rollout/backpropagate
+        passes to run per move. `exploration` is UCT's `c`, trading off
+        exploiting the current best-looking move against trying
+        under-visited ones; the textbook default is `sqrt(2)`.
+        `max_rollout_plies` caps how far a rollout plays on before it
+        is scored as a draw, so a rollout can never run away in a game
+        that happens not to terminate quickly. `preferences` is the
+        same "who each player wants to win" vector `CleverPlayer` takes;
+        pass `vec![]` for ordinary win/lose scoring.
+    */
+    pub fn new(iterations: usize, exploration: f64, max_rollout_plies: i64, preferences: Vec<Vec<usize>>, seed: u64) -> MctsPlayer {
+        MctsPlayer {
+            iterations,
+            exploration,
+            max_rollout_plies,
+            preferences,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /**
+        The reward `mover` gets for `winner` having won (or -1 for a
+        draw), back-propagated up the tree. An outright win for `mover`
+        scores 1.0 and a draw scores 0.5. A win for somebody else scores
+        0.0, unless `preferences` names a ranked list of winners `mover`
+        would rather see: then the reward fades from just under a draw's
+        down towards 0.0 the further down `mover`'s list the actual
+        winner falls, so the tree still prefers lines that let a more
+        `preferences`-favoured opponent win.
+    */
+    fn _reward(&self, mover: usize, winner: i64) -> f64 {
+        if winner < 0 {
+            return 0.5;
+        }
+        if winner as usize == mover {
+            return 1.0;
+        }
+        if !self.preferences.is_empty() {
+            let p = &self.preferences[mover];
+            if let Some(f) = p.iter().position(|&x| x == winner as usize) {
+                return 0.5 * (1.0 - (f as f64 + 1.0) / (p.len() as f64 + 1.0));
+            }
+        }
+        0.0
+    }
+
+    /**
+        Resolves an unforced `has_card` question during expansion or
+        rollout: there is no opponent model here (unlike `CleverPlayer`,
+        which recurses to find out), so a coin flip is as good a guess
+        as any, matching how `RandomPlayer` answers the same question.
+    */
+    fn _guess_has_card(&mut self, cards: &Cards, suit: i8, this: usize, other: usize) -> bool {
+        let (forced, has) = cards.has_card(suit, this, other);
+        if forced {
+            return has;
+        }
+        self.rng.gen_bool(0.5)
+    }
+
+    /** UCT score of `child`, as seen while choosing among `parent`'s children. */
+    fn _uct_score(&self, parent_visits: u64, child: &MctsNode) -> f64 {
+        let exploitation = child.wins / child.visits as f64;
+        let exploration = self.exploration * ((parent_visits as f64).ln() / child.visits as f64).sqrt();
+        exploitation + exploration
+    }
+
+    /**
+        Walks down from `root` while every visited node is fully
+        expanded, each time picking the child maximizing `_uct_score`,
+        and returns the index of the first node it reaches that still
+        has an untried move (or a terminal node with no legal moves at
+        all).
+    */
+    fn _select(&self, arena: &Vec<MctsNode>, root: usize) -> usize {
+        let mut current = root;
+        loop {
+            let node = &arena[current];
+            if !node.is_fully_expanded() || node.children.is_empty() {
+                return current;
+            }
+            let parent_visits = node.visits;
+            current = *node.children.iter().max_by(|&&a, &&b| {
+                self._uct_score(parent_visits, &arena[a])
+                    .partial_cmp(&self._uct_score(parent_visits, &arena[b]))
+                    .unwrap()
+            }).unwrap();
+        }
+    }
+
+    /**
+        Expands one untried move from `arena[leaf]`, applying the
+        heuristic `has_card` guess to get a concrete child position, and
+        returns the new child's index. Panics if `leaf` has no untried
+        moves; callers only expand nodes `_select` returned as
+        not-fully-expanded.
+    */
+    fn _expand(&mut self, arena: &mut Vec<MctsNode>, leaf: usize) -> usize {
+        let (other, suit) = arena[leaf].untried.pop().unwrap();
+        let this = arena[leaf].this;
+        let mut cards = arena[leaf].cards.clone();
+        let has = self._guess_has_card(&cards, suit, other, this);
+        if has {
+            cards.transfer(suit, other, this, false);
+        } else {
+            cards.no_transfer(suit, other, this, false);
+        }
+        let winner = cards.test_winner(this);
+        let mut history = arena[leaf].history.clone();
+        let (next_player, terminal) = if winner == ILLEGAL_CARDS {
+            (this, Some(NO_WINNER))
+        } else if winner != NO_WINNER {
+            (this, Some(winner))
+        } else {
+            let next_player = cards.next_player(this);
+            let position = cards.position(next_player);
+            if history.contains(&position) {
+                (next_player, Some(NO_WINNER))
+            } else {
+                history.insert(position);
+                (next_player, None)
+            }
+        };
+        let child = MctsNode::new(next_player, Some(this), Some((other, suit)), cards, history, Some(leaf), terminal);
+        let child_index = arena.len();
+        arena.push(child);
+        arena[leaf].children.push(child_index);
+        child_index
+    }
+
+    /**
+        Plays random legal moves on from `node`'s position until
+        `test_winner` resolves it, the position repeats (a draw, per
+        `history`), or `max_rollout_plies` runs out (also scored as a
+        draw), and returns the winner (or -1).
+    */
+    fn _rollout(&mut self, node: &MctsNode) -> i64 {
+        if let Some(winner) = node.terminal {
+            return winner;
+        }
+        let mut cards = node.cards.clone();
+        let mut history = node.history.clone();
+        let mut this = node.this;
+        let permutation: Vec<i8> = (0..cards.config.number_of_suits).collect();
+        for _ in 0..self.max_rollout_plies {
+            let moves = cards.legal_moves_given_permutation(this, &permutation);
+            assert!(!moves.is_empty());
+            let i = self.rng.gen_range(0..moves.len());
+            let (other, suit) = moves[i];
+            let has = self._guess_has_card(&cards, suit, other, this);
+            if has {
+                cards.transfer(suit, other, this, false);
+            } else {
+                cards.no_transfer(suit, other, this, false);
+            }
+            let winner = cards.test_winner(this);
+            if winner == ILLEGAL_CARDS {
+                return NO_WINNER;
+            }
+            if winner != NO_WINNER {
+                return winner;
+            }
+            let next_player = cards.next_player(this);
+            let position = cards.position(next_player);
+            if history.contains(&position) {
+                return NO_WINNER;
+            }
+            history.insert(position);
+            this = next_player;
+        }
+        NO_WINNER
+    }
+
+    /** Adds one visit and `self._reward(mover, winner)` (for nodes with a `mover`) up the path from `node` to the root. */
+    fn _backpropagate(&self, arena: &mut Vec<MctsNode>, node: usize, winner: i64) {
+        let mut current = Some(node);
+        while let Some(i) = current {
+            let mover = arena[i].mover;
+            arena[i].visits += 1;
+            if let Some(m) = mover {
+                arena[i].wins += self._reward(m, winner);
+            }
+            current = arena[i].parent;
+        }
+    }
+
+    /**
+        Runs `self.iterations` select/expand/rollout/backpropagate
+        passes rooted at `this`'s current position, and returns the
+        root's most-visited child's move.
+    */
+    fn _search(&mut self, this: usize, cards: &Cards, history: &HashSet<i128>) -> (usize, i8) {
+        let mut arena = vec![MctsNode::new(this, None, None, cards.clone(), history.clone(), None, None)];
+        assert!(!arena[0].untried.is_empty());
+
+        for _ in 0..self.iterations {
+            let leaf = self._select(&arena, 0);
+            let rollout_from = if arena[leaf].untried.is_empty() {
+                leaf
+            } else {
+                self._expand(&mut arena, leaf)
+            };
+            let winner = self._rollout(&arena[rollout_from]);
+            self._backpropagate(&mut arena, rollout_from, winner);
+        }
+
+        let best_child = *arena[0].children.iter().max_by_key(|&&c| arena[c].visits).unwrap();
+        arena[best_child].mover_move.expect("every non-root node has a move that led to it")
+    }
+}
+
+impl Player for MctsPlayer {
+    fn next_move(&mut self, this: usize, cards: &Cards, history: &HashSet<i128>) -> (usize, i8) {
+        self._search(this, cards, history)
+    }
+
+    /**
+        Unlike `next_move`, this does not grow a search tree -- there is
+        no "most-visited move" to pick among for a yes/no question, so,
+        as with `RandomPlayer`, any unforced answer is just a coin flip.
+    */
+    fn has_card(&mut self, this: usize, other: usize, suit: i8, cards: &Cards, _history: &HashSet<i128>) -> bool {
+        self._guess_has_card(cards, suit, this, other)
+    }
+
+    fn info(&self) -> String {
+        format!("mcts iterations: {}, exploration: {}", self.iterations, self.exploration)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{CleverPlayer, HumanPlayer, Player};
+    use super::{CleverPlayer, EpsilonPlayer, HumanPlayer, MctsPlayer, Player, RandomPlayer};
     use game::play;
     use std::time::Instant;
     
@@ -399,9 +1270,42 @@ mod tests {
         println!();
     }
 
+    #[test]
+    pub fn test_clever_player_with_time_budget_finishes_a_game() {
+        use std::time::Duration;
+        let clever: Box<Player> = Box::new(
+            CleverPlayer::new(1000, 1000, 0, vec![], true, 1)
+                .with_time_budget(Duration::from_millis(200)));
+        let mut players = vec![clever];
+        let result = play(&[0, 0], &mut players);
+        assert!(result == -1, "test_clever_player_with_time_budget_finishes_a_game: expecting a draw");
+        println!("{}", players[0].info());
+    }
+
+    #[test]
+    pub fn test_clever_player_with_time_budget_does_not_hang_on_its_mandatory_depth_0_iteration() {
+        use std::time::{Duration, Instant};
+        // A tiny time budget leaves `_evaluate_move_timed`'s mandatory,
+        // deadline-less depth-0 iteration as the only one that actually
+        // gets to run. That iteration must not recurse (given_depth goes
+        // negative past it), or the "always completes" promise in its
+        // own doc comment is broken and the move takes far longer than
+        // the budget asked for.
+        let clever: Box<Player> = Box::new(
+            CleverPlayer::new(1000, 1000, 0, vec![], true, 1)
+                .with_time_budget(Duration::from_millis(1)));
+        let mut players = vec![clever];
+        let start = Instant::now();
+        play(&[0, 0, 0], &mut players);
+        let elapsed = start.elapsed();
+        assert!(elapsed < Duration::from_secs(5),
+            "test_clever_player_with_time_budget_does_not_hang_on_its_mandatory_depth_0_iteration: \
+            took {:?}, expected the depth-0 iteration to bottom out almost immediately", elapsed);
+    }
+
     #[test]
     pub fn test_two_clever_players() {
-        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 0, vec![], true));
+        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 0, vec![], true, 1));
         let mut players = vec![clever];
         let result = play(&[0, 0], &mut players);
         if result == -1 {
@@ -414,10 +1318,95 @@ mod tests {
         println!("----------------");
         println!();
     }
-    
+
+    #[test]
+    pub fn test_two_epsilon_players_finishes() {
+        let random = RandomPlayer::new(1);
+        let clever = CleverPlayer::new(1000, 1000, 0, vec![], true, 1);
+        let epsilon: Box<Player> = Box::new(EpsilonPlayer::new(0.5, random, clever, 2));
+        let mut players = vec![epsilon];
+        let result = play(&[0, 0], &mut players);
+        if result == -1 {
+            println!("Result is a draw");
+        } else {
+            println!("Win for player {}", result);
+        }
+        println!("{}", players[0].info());
+        println!("----------------");
+        println!();
+    }
+
+    #[test]
+    pub fn test_epsilon_player_last_evaluated_result_is_none_after_a_random_move() {
+        use cards::Cards;
+        use std::collections::HashSet;
+        let random = RandomPlayer::new(1);
+        let clever = CleverPlayer::new(1000, 1000, 0, vec![], true, 1);
+        // epsilon = 1.0 always defers to random, so every move this
+        // player returns comes from `random`, never `clever`.
+        let mut epsilon = EpsilonPlayer::new(1.0, random, clever, 2);
+        let cards = Cards::new(2);
+        let history = HashSet::new();
+        epsilon.next_move(0, &cards, &history);
+        assert_eq!(epsilon.last_evaluated_result(), None);
+    }
+
+    #[test]
+    pub fn test_epsilon_player_last_evaluated_result_survives_an_interleaved_has_card_call() {
+        use cards::Cards;
+        use std::collections::HashSet;
+        let random = RandomPlayer::new(1);
+        let clever = CleverPlayer::new(1000, 1000, 0, vec![], true, 1);
+        // epsilon = 0.5, seed = 9 picks `clever` for the first deferral
+        // decision and `random` for the second: this is the "epsilon
+        // epsilon" CLI idiom, where one EpsilonPlayer instance answers
+        // for two seats, and the game loop calls next_move for seat 0
+        // then has_card for seat 1 before reading seat 0's result.
+        let mut epsilon = EpsilonPlayer::new(0.5, random, clever, 9);
+        let cards = Cards::new(2);
+        let history = HashSet::new();
+        epsilon.next_move(0, &cards, &history);
+        assert!(epsilon.last_evaluated_result().is_some());
+        epsilon.has_card(1, 0, 0, &cards, &history);
+        assert!(epsilon.last_evaluated_result().is_some());
+    }
+
+    #[test]
+    pub fn test_five_mcts_players_finishes() {
+        let mcts: Box<Player> = Box::new(MctsPlayer::new(200, 2.0f64.sqrt(), 100, vec![], 1));
+        let mut players = vec![mcts];
+        let result = play(&[0, 0, 0, 0, 0], &mut players);
+        if result == -1 {
+            println!("Result is a draw");
+        } else {
+            println!("Win for player {}", result);
+        }
+        println!("{}", players[0].info());
+        println!("----------------");
+        println!();
+    }
+
+    #[test]
+    pub fn test_best_line_reaches_a_resolved_outcome() {
+        use cards::Cards;
+        use std::collections::HashSet;
+        let mut clever = CleverPlayer::new(1000, 1000, 0, vec![], true, 1);
+        let cards = Cards::new(2);
+        let history = HashSet::new();
+        let (other, suit) = clever.next_move(0, &cards, &history);
+        let line = clever.best_line(0, &cards, &history);
+        assert!(!line.is_empty(), "test_best_line_reaches_a_resolved_outcome: expecting a non-empty line");
+        assert_eq!(line[0].0, 0);
+        assert_eq!(line[0].1, other);
+        assert_eq!(line[0].2, suit);
+        for step in &line {
+            println!("player {} asks player {} for suit {}: {}", step.0, step.1, step.2, step.3);
+        }
+    }
+
     #[test]
     pub fn test_three_clever_players() {
-        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 0, vec![], true));
+        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 0, vec![], true, 1));
         let mut players = vec![clever];
         let result = play(&[0, 0, 0], &mut players);
         if result == -1 {
@@ -432,7 +1421,7 @@ mod tests {
     
     pub fn three_biased_players(preferences: Vec<Vec<usize>>, symmetric: bool) -> i64 {
         let result;
-        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 0, preferences, symmetric));
+        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 0, preferences, symmetric, 1));
         let mut players = vec![clever];
         result = play(&[0, 0, 0], &mut players);
         if result == -1 {
@@ -487,7 +1476,7 @@ mod tests {
             vec![0, 3],
             vec![1, 0],
             vec![2, 1],
-            ], true));
+            ], true, 1));
         let mut players = vec![clever];
         result = play(&[0, 0, 0, 0], &mut players);
         if result == -1 {
@@ -517,7 +1506,7 @@ mod tests {
             preferences.push(subprefs);
         }
 
-        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 1, preferences, true));
+        let clever: Box<Player> = Box::new(CleverPlayer::new(1000, 1000, 1, preferences, true, 1));
         let mut players = vec![clever];
         result = play(&[0, 0, 0, 0], &mut players);
         if result == -1 {
@@ -559,7 +1548,7 @@ mod tests {
             vec![1, 0, 4],
             vec![2, 1, 0],
             vec![3, 2, 1],
-            ], true));
+            ], true, 1));
         let mut players = vec![clever];
         let result = play(&[0, 0, 0, 0, 0], &mut players);
         if result == -1 {
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let t = b;
+        b = a % b;
+        a = t;
+    }
+    a
+}
+
+/**
+    An exact fraction of two `i128`s, always stored reduced to lowest
+    terms with a positive denominator, so that long chains of
+    deduction never accumulate floating-point error and two equal
+    probabilities compare equal with `==`.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Rational {
+        assert!(denominator != 0, "Rational denominator must not be zero");
+        let sign = if denominator < 0 { -1 } else { 1 };
+        let divisor = gcd(numerator, denominator).max(1);
+        Rational {
+            numerator: sign * numerator / divisor,
+            denominator: sign * denominator / divisor,
+        }
+    }
+
+    pub fn zero() -> Rational {
+        Rational { numerator: 0, denominator: 1 }
+    }
+
+    pub fn one() -> Rational {
+        Rational { numerator: 1, denominator: 1 }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+    fn add(self, other: Rational) -> Rational {
+        Rational::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+    fn sub(self, other: Rational) -> Rational {
+        Rational::new(
+            self.numerator * other.denominator - other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+    fn mul(self, other: Rational) -> Rational {
+        Rational::new(self.numerator * other.numerator, self.denominator * other.denominator)
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+    fn div(self, other: Rational) -> Rational {
+        assert!(other.numerator != 0, "cannot divide by zero");
+        Rational::new(self.numerator * other.denominator, self.denominator * other.numerator)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_reduces_to_lowest_terms() {
+        let r = Rational::new(6, 8);
+        assert_eq!(r.numerator(), 3);
+        assert_eq!(r.denominator(), 4);
+    }
+
+    #[test]
+    fn test_new_normalizes_negative_denominator() {
+        let r = Rational::new(1, -2);
+        assert_eq!(r.numerator(), -1);
+        assert_eq!(r.denominator(), 2);
+    }
+
+    #[test]
+    fn test_add_and_sub() {
+        let a = Rational::new(1, 3);
+        let b = Rational::new(1, 6);
+        assert_eq!(a + b, Rational::new(1, 2));
+        assert_eq!(a - b, Rational::new(1, 6));
+    }
+
+    #[test]
+    fn test_mul_and_div() {
+        let a = Rational::new(2, 3);
+        let b = Rational::new(3, 4);
+        assert_eq!(a * b, Rational::new(1, 2));
+        assert_eq!(a / b, Rational::new(8, 9));
+    }
+
+    #[test]
+    fn test_equal_fractions_compare_equal() {
+        assert_eq!(Rational::new(2, 4), Rational::new(1, 2));
+    }
+}
@@ -0,0 +1,123 @@
+use game::play_logged;
+use player::Player;
+use std::panic::{self, AssertUnwindSafe};
+
+/**
+    Aggregated results of running the same player lineup through many
+    games. `wins[p]` is the number of games won by seat `p`.
+*/
+pub struct SimulationResult {
+    pub games: usize,
+    pub wins: Vec<usize>,
+    pub draws: usize,
+    pub illegal: usize,
+    pub total_length: usize,
+}
+
+impl SimulationResult {
+    fn new(number_of_players: usize) -> SimulationResult {
+        SimulationResult {
+            games: 0,
+            wins: vec![0; number_of_players],
+            draws: 0,
+            illegal: 0,
+            total_length: 0,
+        }
+    }
+
+    pub fn draw_rate(&self) -> f64 {
+        self.draws as f64 / self.games as f64
+    }
+
+    pub fn illegal_rate(&self) -> f64 {
+        self.illegal as f64 / self.games as f64
+    }
+
+    pub fn average_length(&self) -> f64 {
+        self.total_length as f64 / (self.games - self.illegal) as f64
+    }
+
+    pub fn win_rate(&self, seat: usize) -> f64 {
+        self.wins[seat] as f64 / self.games as f64
+    }
+
+    pub fn print_table(&self) {
+        println!("games: {}", self.games);
+        for seat in 0..self.wins.len() {
+            println!("  seat {} wins: {} ({:.1}%)", seat, self.wins[seat], 100.0 * self.win_rate(seat));
+        }
+        println!("  draws: {} ({:.1}%)", self.draws, 100.0 * self.draw_rate());
+        println!("  illegal: {} ({:.1}%)", self.illegal, 100.0 * self.illegal_rate());
+        println!("  average length: {:.1}", self.average_length());
+    }
+}
+
+/**
+    Runs `games` games with the given lineup, reusing `player_instances`
+    across every game so any internal caches (e.g. `CleverPlayer`'s
+    transposition cache) stay warm. `ILLEGAL_CARDS` would normally cause
+    `play` to panic; here each occurrence is counted instead, and the
+    game is excluded from the length/win/draw statistics.
+*/
+pub fn simulate(players: &[usize], player_instances: &mut [Box<Player>], games: usize) -> SimulationResult {
+    let mut result = SimulationResult::new(players.len());
+    // Silence the default panic hook while we are deliberately catching
+    // the ILLEGAL_CARDS panic below; otherwise every illegal game would
+    // still print a backtrace to stderr.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    for _ in 0..games {
+        result.games += 1;
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| play_logged(players, player_instances)));
+        match outcome {
+            Ok((winner, log, _transcript)) => {
+                result.total_length += log.entries.len();
+                if winner == -1 {
+                    result.draws += 1;
+                } else {
+                    result.wins[winner as usize] += 1;
+                }
+            }
+            Err(_) => {
+                result.illegal += 1;
+            }
+        }
+    }
+
+    panic::set_hook(default_hook);
+    result
+}
+
+/**
+    A preference vector and the simulation result it produced, as
+    printed by a sweep over several configurations.
+*/
+pub struct SweepRow {
+    pub prefs: Vec<Vec<usize>>,
+    pub result: SimulationResult,
+}
+
+/**
+    Runs `simulate` once per preference vector in `prefs_sweep`, building
+    fresh player instances each time with `build_players`, and prints a
+    win-rate table per configuration. `build_players` takes the
+    preferences for this configuration and returns the seat list plus
+    the player instances to use (so callers can freely mix
+    `CleverPlayer`, `RandomPlayer` etc).
+*/
+pub fn sweep_prefs(
+        prefs_sweep: &[Vec<Vec<usize>>],
+        games: usize,
+        mut build_players: impl FnMut(&[Vec<usize>]) -> (Vec<usize>, Vec<Box<Player>>)) -> Vec<SweepRow> {
+
+    let mut rows = Vec::new();
+    for prefs in prefs_sweep {
+        let (players, mut player_instances) = build_players(prefs);
+        let result = simulate(&players, player_instances.as_mut_slice(), games);
+        println!("prefs: {:?}", prefs);
+        result.print_table();
+        rows.push(SweepRow { prefs: prefs.clone(), result });
+    }
+    rows
+}
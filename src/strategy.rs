@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+use cards::{Cards, NO_WINNER, ILLEGAL_CARDS};
+use player::{CleverPlayer, Player};
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/**
+    A lighter-weight alternative to `Player`: a `Strategy` only has to
+    pick a move and decide whether to hand over a card, without
+    threading `this`/history bookkeeping through every call. One
+    instance is owned per seat, so implementations that need to
+    remember their own seat or build up state across turns can store
+    it themselves.
+*/
+pub trait Strategy {
+    /**
+        Choose a move for seat `me`: who to ask, and for which suit.
+    */
+    fn choose_move(&mut self, cards: &Cards, me: usize) -> (usize, i8);
+    /**
+        Decide whether to hand over a card of `suit` to `asker`.
+    */
+    fn decide_reply(&mut self, cards: &Cards, suit: i8, asker: usize) -> bool;
+}
+
+/**
+    Picks uniformly among the legal moves. When asked for a card, gives
+    the forced answer if the hand's deductions already settle it, and
+    otherwise answers with a coin flip.
+*/
+pub struct RandomStrategy {
+    me: usize,
+    rng: StdRng,
+}
+
+impl RandomStrategy {
+    pub fn new(me: usize, seed: u64) -> RandomStrategy {
+        RandomStrategy { me, rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn choose_move(&mut self, cards: &Cards, me: usize) -> (usize, i8) {
+        let n = cards.config.number_of_suits;
+        let permutation: Vec<i8> = (0..n).collect();
+        let moves = cards.legal_moves_given_permutation(me, &permutation);
+        assert!(!moves.is_empty());
+        let i = self.rng.gen_range(0..moves.len());
+        moves[i]
+    }
+
+    fn decide_reply(&mut self, cards: &Cards, suit: i8, asker: usize) -> bool {
+        let (forced, has) = cards.has_card(suit, self.me, asker);
+        if forced {
+            return has;
+        }
+        self.rng.gen_bool(0.5)
+    }
+}
+
+/**
+    Plays optimally by delegating to a `CleverPlayer`'s minimax search
+    over `shake_down`-reduced positions. Remembers its own seat and
+    keeps a running history of canonical positions it has observed, so
+    repeated-position draws are still visible to the search even
+    though `Strategy` does not thread a shared history through every
+    call.
+*/
+pub struct OptimalStrategy {
+    me: usize,
+    inner: CleverPlayer,
+    history: HashSet<i128>,
+}
+
+impl OptimalStrategy {
+    pub fn new(me: usize, max_depth: i64, max_has_depth: i64) -> OptimalStrategy {
+        OptimalStrategy {
+            me,
+            inner: CleverPlayer::new(max_depth, max_has_depth, 0, vec![], true, 1),
+            history: HashSet::new(),
+        }
+    }
+}
+
+impl Strategy for OptimalStrategy {
+    fn choose_move(&mut self, cards: &Cards, me: usize) -> (usize, i8) {
+        self.history.insert(cards.canonical_position(me));
+        self.inner.next_move(me, cards, &self.history)
+    }
+
+    fn decide_reply(&mut self, cards: &Cards, suit: i8, asker: usize) -> bool {
+        self.history.insert(cards.canonical_position(self.me));
+        self.inner.has_card(self.me, asker, suit, cards, &self.history)
+    }
+}
+
+/**
+    Runs `strategies` (one per seat) to completion, exactly as `game::play`
+    does for `Player`s, and returns the winning seat, or -1 for a draw.
+*/
+pub fn play_strategies(strategies: &mut [Box<Strategy>]) -> i64 {
+    let number_of_players = strategies.len();
+    let mut cards = Cards::new(number_of_players);
+    let mut history = HashSet::new();
+
+    loop {
+        for i in 0..number_of_players {
+            cards.show(i);
+            if cards.is_empty(i) {
+                println!("Player {} must skip as they have no cards", i);
+                continue;
+            }
+            let (other, suit) = strategies[i].choose_move(&cards, i);
+            println!("Player {} requests suit {} from player {}", i, suit, other);
+            let transfer = strategies[other].decide_reply(&cards, suit, i);
+            if transfer {
+                println!("Player {} hands card {} to player {}", other, suit, i);
+                cards.transfer(suit, other, i, false);
+            } else {
+                println!("Player {} has no cards of suit {}", other, suit);
+                cards.no_transfer(suit, other, i, false);
+            }
+            let winner = cards.test_winner(i);
+            if winner == ILLEGAL_CARDS {
+                cards.show(usize::max_value());
+                panic!("The cards are in an illegal state. All players lose");
+            }
+            if winner != NO_WINNER {
+                cards.show(usize::max_value());
+                return winner;
+            }
+            let position = cards.canonical_position(i);
+            if history.contains(&position) {
+                cards.show(usize::max_value());
+                return -1;
+            }
+            history.insert(position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_two_random_strategies() {
+        let mut strategies: Vec<Box<Strategy>> = vec![
+            Box::new(RandomStrategy::new(0, 1)),
+            Box::new(RandomStrategy::new(1, 2)),
+        ];
+        let result = play_strategies(&mut strategies);
+        assert!(result == -1 || result == 0 || result == 1);
+    }
+
+    #[test]
+    pub fn test_optimal_beats_or_draws_random() {
+        let mut strategies: Vec<Box<Strategy>> = vec![
+            Box::new(OptimalStrategy::new(0, 1000, 1000)),
+            Box::new(RandomStrategy::new(1, 3)),
+        ];
+        let result = play_strategies(&mut strategies);
+        assert!(result != 1, "optimal strategy should never lose to random");
+    }
+}
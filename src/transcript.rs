@@ -0,0 +1,80 @@
+use std::fs::File;
+use std::io::Write;
+
+/**
+    One turn of a `Transcript`: who asked whom for what suit, whether
+    the target actually had it, whether that answer was forced by
+    `shake_down`'s deductions rather than the responding player's own
+    judgement, and the asking player's evaluated outcome for this move
+    (see `Player::last_evaluated_result`), if it performs any search.
+*/
+pub struct TranscriptEntry {
+    pub turn: usize,
+    pub player: usize,
+    pub other: usize,
+    pub suit: i8,
+    pub has_card: bool,
+    pub forced: bool,
+    pub result: Option<i64>,
+}
+
+impl TranscriptEntry {
+    pub fn new(turn: usize, player: usize, other: usize, suit: i8, has_card: bool, forced: bool, result: Option<i64>) -> TranscriptEntry {
+        TranscriptEntry { turn, player, other, suit, has_card, forced, result }
+    }
+
+    fn to_json(&self) -> String {
+        let result = match self.result {
+            Some(r) => r.to_string(),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"turn\":{},\"player\":{},\"other\":{},\"suit\":{},\"has_card\":{},\"forced\":{},\"result\":{}}}",
+            self.turn, self.player, self.other, self.suit, self.has_card, self.forced, result)
+    }
+}
+
+/**
+    A machine-readable, per-turn record of a game, suitable for feeding
+    into notebooks/visualizers or for diffing engine decisions across
+    versions. Unlike `GameLog`, this is not meant to be replayed -- it
+    carries extra analysis fields (`forced`, `result`) that `replay_log`
+    has no use for -- so it exists purely as a separate, read-only
+    transcript.
+*/
+pub struct Transcript {
+    pub entries: Vec<TranscriptEntry>,
+    pub result: i64,
+}
+
+impl Transcript {
+    pub fn new() -> Transcript {
+        Transcript { entries: Vec::new(), result: -1 }
+    }
+
+    pub fn push(&mut self, entry: TranscriptEntry) {
+        self.entries.push(entry);
+    }
+
+    /**
+        Serializes the whole game as a JSON array: one object per turn,
+        in order, followed by a trailing `{"winner": ...}` record (-1
+        for a draw).
+    */
+    pub fn to_json(&self) -> String {
+        let mut s = String::new();
+        s.push('[');
+        for entry in &self.entries {
+            s.push_str(&entry.to_json());
+            s.push(',');
+        }
+        s.push_str(&format!("{{\"winner\":{}}}", self.result));
+        s.push(']');
+        s
+    }
+
+    pub fn write_to_file(&self, path: &str) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_json().as_bytes())
+    }
+}
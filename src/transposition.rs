@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/** Number of shards the table is split across, so concurrent searchers don't fight over one lock. */
+const SHARDS: usize = 32;
+
+/**
+    A memoization cache keyed on an exact, collision-free `i128`
+    position (e.g. `Cards::position_given_permutation`'s canonical
+    key), but probed through a cheap 64-bit `quick_hash` (e.g.
+    `Cards::zobrist_hash`) so that the caller need not have the exact
+    key in hand at every node. Each shard keeps at most one entry per
+    `quick_hash` bucket: a lookup only counts as a hit when the stored
+    exact key also matches, so a `quick_hash` collision between two
+    different positions just costs a miss, never a wrong answer.
+
+    Each shard is capped at `capacity / SHARDS` entries, and clears
+    itself outright once full -- a deliberately simple eviction policy,
+    since the only harm in evicting too eagerly is an extra cache miss.
+*/
+pub struct TranspositionTable<V: Clone> {
+    shards: Vec<Mutex<HashMap<u64, (i128, V)>>>,
+    capacity_per_shard: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<V: Clone> TranspositionTable<V> {
+    pub fn new(capacity: usize) -> TranspositionTable<V> {
+        TranspositionTable {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            capacity_per_shard: (capacity / SHARDS).max(1),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn shard_for(quick_hash: u64) -> usize {
+        (quick_hash % SHARDS as u64) as usize
+    }
+
+    /** Looks up `exact_key`, pre-filtered by `quick_hash`. Updates the hit/miss stat counters. */
+    pub fn get(&self, quick_hash: u64, exact_key: i128) -> Option<V> {
+        let shard = self.shards[Self::shard_for(quick_hash)].lock().unwrap();
+        let found = match shard.get(&quick_hash) {
+            Some((key, value)) if *key == exact_key => Some(value.clone()),
+            _ => None,
+        };
+        if found.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        found
+    }
+
+    pub fn insert(&self, quick_hash: u64, exact_key: i128, value: V) {
+        let mut shard = self.shards[Self::shard_for(quick_hash)].lock().unwrap();
+        if shard.len() >= self.capacity_per_shard && !shard.contains_key(&quick_hash) {
+            shard.clear();
+        }
+        shard.insert(quick_hash, (exact_key, value));
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().unwrap().len()).sum()
+    }
+
+    /** Returns (hits, misses) observed by `get` so far. */
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_then_get_is_a_hit() {
+        let table: TranspositionTable<i8> = TranspositionTable::new(1024);
+        table.insert(42, 12345, 7);
+        assert_eq!(table.get(42, 12345), Some(7));
+        assert_eq!(table.stats(), (1, 0));
+    }
+
+    #[test]
+    fn test_quick_hash_collision_with_different_exact_key_is_a_miss() {
+        let table: TranspositionTable<i8> = TranspositionTable::new(1024);
+        table.insert(42, 12345, 7);
+        assert_eq!(table.get(42, 99999), None);
+        assert_eq!(table.stats(), (0, 1));
+    }
+
+    #[test]
+    fn test_missing_quick_hash_is_a_miss() {
+        let table: TranspositionTable<i8> = TranspositionTable::new(1024);
+        assert_eq!(table.get(1, 1), None);
+        assert_eq!(table.stats(), (0, 1));
+    }
+
+    #[test]
+    fn test_shard_fills_up_and_evicts() {
+        let table: TranspositionTable<i8> = TranspositionTable::new(SHARDS);
+        // Every key below maps to shard 0, so capacity_per_shard is 1: the
+        // second insert should evict the first rather than grow unbounded.
+        table.insert(0, 1, 1);
+        table.insert(SHARDS as u64, 2, 2);
+        assert!(table.len() <= 1);
+    }
+}